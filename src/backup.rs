@@ -0,0 +1,125 @@
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use chrono::NaiveDateTime;
+
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::persistence::{self, ARCHIVE_FILE, TASKS_FILE};
+use crate::task::Task;
+
+pub const BACKUP_DIRECTORY: &str = "backups";
+// Number of backups kept unless configured otherwise
+pub const DEFAULT_BACKUP_RETENTION: usize = 10;
+
+// Backups are named by when they were made, e.g. 20261019-143005
+const BACKUP_TIME_FORMAT: &str = "%Y%m%d-%H%M%S";
+
+pub struct Backup {
+    pub name: String,
+    pub time: NaiveDateTime,
+}
+
+impl Backup {
+    fn path(&self, file: &str) -> String {
+        format!("{}/{}/{}", BACKUP_DIRECTORY, self.name, file)
+    }
+
+    // The tasks and archived tasks as they were when the backup was made
+    pub fn load(&self) -> Result<(Vec<Task>, Vec<Task>), Box<dyn Error>> {
+        let mut tasks = Vec::new();
+        let mut archived_tasks = Vec::new();
+
+        for (file, loaded) in [(TASKS_FILE, &mut tasks), (ARCHIVE_FILE, &mut archived_tasks)] {
+            let path = self.path(file);
+            if Path::new(&path).exists() {
+                *loaded = persistence::load_tasks_from_csv(&path)?.tasks;
+            }
+        }
+
+        Ok((tasks, archived_tasks))
+    }
+}
+
+// Copies tasks.csv and archive.csv into a new backup before they are
+// overwritten, then removes the oldest backups beyond retention. Makes no
+// backup with a retention of 0 or while there is nothing stored yet.
+pub fn create_backup(retention: usize) -> Result<Option<Backup>, Box<dyn Error>> {
+    if !Path::new(TASKS_FILE).exists() {
+        return Ok(None);
+    }
+
+    new_backup(retention, |backup| {
+        for file in [TASKS_FILE, ARCHIVE_FILE] {
+            if Path::new(file).exists() {
+                fs::copy(file, backup.path(file))?;
+            }
+        }
+        Ok(())
+    })
+}
+
+// Backs up tasks that aren't stored in files, by writing them like tasks.csv
+// and archive.csv would be
+pub fn create_backup_of(
+    retention: usize,
+    tasks: &[Task],
+    archived_tasks: &[Task],
+) -> Result<Option<Backup>, Box<dyn Error>> {
+    new_backup(retention, |backup| {
+        persistence::save_tasks_to_csv(&backup.path(TASKS_FILE), tasks)?;
+        persistence::save_tasks_to_csv(&backup.path(ARCHIVE_FILE), archived_tasks)
+    })
+}
+
+fn new_backup<F>(retention: usize, write_files: F) -> Result<Option<Backup>, Box<dyn Error>>
+where
+    F: FnOnce(&Backup) -> Result<(), Box<dyn Error>>,
+{
+    if retention == 0 {
+        return Ok(None);
+    }
+
+    let time = NaiveDateTimeWrapper::now().0;
+    let backup = Backup {
+        name: time.format(BACKUP_TIME_FORMAT).to_string(),
+        time,
+    };
+
+    // Saved twice within a second, the first backup has the older state
+    let directory = format!("{}/{}", BACKUP_DIRECTORY, backup.name);
+    if !Path::new(&directory).exists() {
+        fs::create_dir_all(&directory)?;
+        write_files(&backup)?;
+    }
+
+    let backups = list_backups()?;
+    for old_backup in backups.iter().take(backups.len().saturating_sub(retention)) {
+        fs::remove_dir_all(format!("{}/{}", BACKUP_DIRECTORY, old_backup.name))?;
+    }
+
+    Ok(Some(backup))
+}
+
+// All backups, oldest first
+pub fn list_backups() -> Result<Vec<Backup>, Box<dyn Error>> {
+    let mut backups = Vec::new();
+
+    if !Path::new(BACKUP_DIRECTORY).exists() {
+        return Ok(backups);
+    }
+
+    for entry in fs::read_dir(BACKUP_DIRECTORY)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if let Ok(time) = NaiveDateTime::parse_from_str(&name, BACKUP_TIME_FORMAT) {
+            backups.push(Backup { name, time });
+        }
+    }
+
+    backups.sort_by_key(|backup| backup.time);
+    Ok(backups)
+}
+
+pub fn find_backup(name: &str) -> Result<Option<Backup>, Box<dyn Error>> {
+    Ok(list_backups()?.into_iter().find(|backup| backup.name == name))
+}
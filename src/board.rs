@@ -0,0 +1,173 @@
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::task::Task;
+use crate::task_list::TaskList;
+use crate::task_status::TaskStatus;
+use crate::tui::status_style;
+
+const HELP_LINE: &str = "←/→ column  ↑/↓ task  </> move task left/right  z undo  q quit";
+
+struct Board {
+    statuses: Vec<TaskStatus>,
+    column: usize,
+    // Selected row in each column
+    rows: Vec<usize>,
+    message: String,
+    quit: bool,
+}
+
+impl Board {
+    fn column_tasks<'a>(&self, list_of_tasks: &'a TaskList, column: usize) -> Vec<&'a Task> {
+        list_of_tasks
+            .active_tasks()
+            .filter(|t| t.status == self.statuses[column])
+            .collect()
+    }
+
+    fn selected_task_id(&self, list_of_tasks: &TaskList) -> Option<usize> {
+        self.column_tasks(list_of_tasks, self.column)
+            .get(self.rows[self.column])
+            .map(|t| t.id)
+    }
+}
+
+// Shows tasks in one column per status until the user quits
+pub fn run(list_of_tasks: &mut TaskList) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run_board(&mut terminal, list_of_tasks);
+    ratatui::restore();
+
+    result
+}
+
+fn run_board(terminal: &mut DefaultTerminal, list_of_tasks: &mut TaskList) -> io::Result<()> {
+    let statuses = TaskStatus::all();
+    let mut board = Board {
+        rows: vec![0; statuses.len()],
+        statuses,
+        column: 0,
+        message: String::new(),
+        quit: false,
+    };
+
+    while !board.quit {
+        terminal.draw(|frame| draw(frame, &mut board, list_of_tasks))?;
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            board.message.clear();
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => board.quit = true,
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    board.quit = true
+                }
+                KeyCode::Left | KeyCode::Char('h') => board.column = board.column.saturating_sub(1),
+                KeyCode::Right | KeyCode::Char('l') => {
+                    board.column = (board.column + 1).min(board.statuses.len() - 1)
+                }
+                KeyCode::Up | KeyCode::Char('k') => {
+                    board.rows[board.column] = board.rows[board.column].saturating_sub(1)
+                }
+                KeyCode::Down | KeyCode::Char('j') => board.rows[board.column] += 1,
+                KeyCode::Char('<') => move_selected_task(&mut board, list_of_tasks, -1),
+                KeyCode::Char('>') => move_selected_task(&mut board, list_of_tasks, 1),
+                KeyCode::Char('z') => {
+                    board.message = match list_of_tasks.undo() {
+                        Some(operation) => format!("Undone: {}", operation),
+                        None => "Nothing to undo".to_string(),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Moves the selected task to the neighbouring column and keeps it selected
+fn move_selected_task(board: &mut Board, list_of_tasks: &mut TaskList, direction: isize) {
+    let task_id = match board.selected_task_id(list_of_tasks) {
+        Some(task_id) => task_id,
+        None => return,
+    };
+
+    let target_column = match board.column.checked_add_signed(direction) {
+        Some(column) if column < board.statuses.len() => column,
+        _ => return,
+    };
+
+    list_of_tasks.update_task_status(task_id, board.statuses[target_column].clone());
+    board.column = target_column;
+    board.rows[target_column] = board
+        .column_tasks(list_of_tasks, target_column)
+        .iter()
+        .position(|t| t.id == task_id)
+        .unwrap_or(0);
+    board.message = format!("Task {} moved to {}", task_id, board.statuses[target_column]);
+}
+
+fn draw(frame: &mut Frame, board: &mut Board, list_of_tasks: &TaskList) {
+    let [columns_area, footer_area] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).areas(frame.area());
+    let column_areas = Layout::horizontal(vec![
+        Constraint::Ratio(1, board.statuses.len() as u32);
+        board.statuses.len()
+    ])
+    .split(columns_area);
+
+    for (column, area) in column_areas.iter().enumerate() {
+        let tasks = board.column_tasks(list_of_tasks, column);
+        board.rows[column] = board.rows[column].min(tasks.len().saturating_sub(1));
+
+        let items: Vec<ListItem> = tasks
+            .iter()
+            .map(|task| {
+                let mut lines = vec![Line::from(format!("{} - {}", task.id, task.title))];
+                if let Some(due_date) = task.due_date {
+                    let style = if task.is_overdue() {
+                        Style::default().fg(Color::Red)
+                    } else {
+                        Style::default().fg(Color::DarkGray)
+                    };
+                    lines.push(Line::from(Span::styled(format!("  due {}", due_date), style)));
+                }
+                ListItem::new(lines)
+            })
+            .collect();
+
+        let status = &board.statuses[column];
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .title(format!(" {} ({}) ", status, tasks.len()))
+            .title_style(status_style(status).add_modifier(Modifier::BOLD));
+        let mut highlight_style = Style::default();
+        let mut list_state = ListState::default();
+
+        if column == board.column {
+            block = block.border_style(Style::default().fg(Color::Cyan));
+            highlight_style = highlight_style.add_modifier(Modifier::REVERSED);
+            if !tasks.is_empty() {
+                list_state.select(Some(board.rows[column]));
+            }
+        }
+
+        let list = List::new(items).block(block).highlight_style(highlight_style);
+        frame.render_stateful_widget(list, *area, &mut list_state);
+    }
+
+    frame.render_widget(
+        Paragraph::new(HELP_LINE).block(Block::default().borders(Borders::ALL).title(board.message.clone())),
+        footer_area,
+    );
+}
@@ -0,0 +1,145 @@
+use chrono::{Datelike, Days, NaiveDate};
+use colorize::AnsiColor;
+
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+fn due_on<'a>(tasks: &[&'a Task], date: NaiveDate) -> Vec<&'a Task> {
+    tasks
+        .iter()
+        .filter(|t| t.due_date.is_some_and(|due_date| due_date.0.date() == date))
+        .copied()
+        .collect()
+}
+
+fn format_task(task: &Task) -> String {
+    let time = task
+        .due_date
+        .map(|due_date| due_date.0.format("%H:%M").to_string())
+        .unwrap_or_default();
+
+    format!("{} {} - {} ({})", time, task.id, task.title, task.status)
+}
+
+// Overdue tasks are listed first in both views so they can't be missed
+pub fn print_overdue(tasks: &[&Task]) {
+    let mut overdue: Vec<&Task> = tasks.iter().filter(|t| t.is_overdue()).copied().collect();
+    if overdue.is_empty() {
+        return;
+    }
+
+    overdue.sort_by_key(|t| t.due_date.map(|due_date| due_date.0));
+
+    println!("{}", "Overdue:".bold().red());
+    for task in overdue {
+        let due_date = task.due_date.map(|d| d.to_string()).unwrap_or_default();
+        println!("{}", format!("  {} - {} (due {})", task.id, task.title, due_date).red());
+    }
+    println!();
+}
+
+// Prints a month grid where days with tasks due are highlighted, followed by
+// the tasks due that month. Months outside of the supported dates are an error.
+pub fn print_month(tasks: &[&Task], year: i32, month: u32) -> Result<(), String> {
+    let first_day = match NaiveDate::from_ymd_opt(year, month, 1) {
+        Some(first_day) => first_day,
+        None => return Err(format!("Invalid month: {:02}.{}", month, year)),
+    };
+    let today = NaiveDateTimeWrapper::now().0.date();
+
+    print_overdue(tasks);
+
+    println!("{}", first_day.format("%B %Y").to_string().bold());
+    println!("Mo Tu We Th Fr Sa Su");
+
+    let mut line = "   ".repeat(first_day.weekday().num_days_from_monday() as usize);
+    let mut date = first_day;
+    while date.month() == month {
+        let due = due_on(tasks, date);
+        let day = format!("{:>2}", date.day());
+
+        let day = if due.iter().any(|t| t.is_overdue()) {
+            day.bold().red()
+        } else if due.iter().any(|t| t.status != TaskStatus::Completed) {
+            day.bold().yellow()
+        } else if !due.is_empty() {
+            day.green()
+        } else if date == today {
+            day.underlined()
+        } else {
+            day
+        };
+        line.push_str(&day);
+        line.push(' ');
+
+        if date.weekday().num_days_from_monday() == 6 {
+            println!("{}", line);
+            line.clear();
+        }
+        date = match date.succ_opt() {
+            Some(next_date) => next_date,
+            None => break,
+        };
+    }
+    if !line.is_empty() {
+        println!("{}", line);
+    }
+    println!();
+
+    let mut date = first_day;
+    while date.month() == month {
+        let due = due_on(tasks, date);
+        if !due.is_empty() {
+            println!("{}", date.format("%a %d.%m.").to_string().bold());
+            for task in due {
+                println!("  {}", format_task(task));
+            }
+        }
+        date = match date.succ_opt() {
+            Some(next_date) => next_date,
+            None => break,
+        };
+    }
+
+    Ok(())
+}
+
+// Prints the tasks due in the next days, grouped by date
+pub fn print_agenda(tasks: &[&Task], days: u32) {
+    let today = NaiveDateTimeWrapper::now().0.date();
+
+    print_overdue(tasks);
+
+    let mut found_any = false;
+    for offset in 0..days {
+        // There are no dates past the last one chrono can represent
+        let date = match today.checked_add_days(Days::new(offset.into())) {
+            Some(date) => date,
+            None => break,
+        };
+        let due: Vec<&Task> = due_on(tasks, date)
+            .into_iter()
+            .filter(|t| !t.is_overdue())
+            .collect();
+        if due.is_empty() {
+            continue;
+        }
+
+        found_any = true;
+        let heading = match offset {
+            0 => format!("Today, {}", date.format("%a %d.%m.%Y")),
+            1 => format!("Tomorrow, {}", date.format("%a %d.%m.%Y")),
+            _ => date.format("%a %d.%m.%Y").to_string(),
+        };
+        println!("{}", heading.bold());
+        for task in due {
+            println!("  {}", format_task(task));
+        }
+        println!();
+    }
+
+    if !found_any {
+        println!("No tasks due in the next {} days", days);
+    }
+}
@@ -3,24 +3,132 @@ use std::str::FromStr;
 pub enum SupportedCommand {
     Add,
     List,
+    Search,
     Update,
     Delete,
+    Trash,
+    Restore,
+    EmptyTrash,
+    Archive,
+    Archived,
+    Unarchive,
+    Undo,
+    Redo,
+    History,
+    AsOf,
+    Tui,
+    Board,
+    Calendar,
+    Agenda,
+    View,
+    Stats,
+    Export,
+    Import,
+    Migrate,
+    Backend,
+    Backup,
+    Config,
     Help,
     Clear,
     Exit
 }
 
-impl ToString for SupportedCommand {
-    fn to_string(&self) -> String {
-        match self {
-            SupportedCommand::Add => String::from("add"),
-            SupportedCommand::List => String::from("list"),
-            SupportedCommand::Update => String::from("update"),
-            SupportedCommand::Delete => String::from("delete"),
-            SupportedCommand::Help => String::from("help"),
-            SupportedCommand::Clear => String::from("clear"),
-            SupportedCommand::Exit => String::from("exit"),
-        }
+impl SupportedCommand {
+    pub fn all() -> Vec<SupportedCommand> {
+        vec![
+            SupportedCommand::Add,
+            SupportedCommand::List,
+            SupportedCommand::Search,
+            SupportedCommand::Update,
+            SupportedCommand::Delete,
+            SupportedCommand::Trash,
+            SupportedCommand::Restore,
+            SupportedCommand::EmptyTrash,
+            SupportedCommand::Archive,
+            SupportedCommand::Archived,
+            SupportedCommand::Unarchive,
+            SupportedCommand::Undo,
+            SupportedCommand::Redo,
+            SupportedCommand::History,
+            SupportedCommand::AsOf,
+            SupportedCommand::Tui,
+            SupportedCommand::Board,
+            SupportedCommand::Calendar,
+            SupportedCommand::Agenda,
+            SupportedCommand::View,
+            SupportedCommand::Stats,
+            SupportedCommand::Export,
+            SupportedCommand::Import,
+            SupportedCommand::Migrate,
+            SupportedCommand::Backend,
+            SupportedCommand::Backup,
+            SupportedCommand::Config,
+            SupportedCommand::Help,
+            SupportedCommand::Clear,
+            SupportedCommand::Exit,
+        ]
+    }
+
+    // Commands that accept their input on the same line, e.g. `delete 3 4 5`
+    pub fn takes_arguments(&self) -> bool {
+        matches!(
+            self,
+            SupportedCommand::Add
+                | SupportedCommand::List
+                | SupportedCommand::Search
+                | SupportedCommand::Update
+                | SupportedCommand::Delete
+                | SupportedCommand::Restore
+                | SupportedCommand::Archive
+                | SupportedCommand::Unarchive
+                | SupportedCommand::History
+                | SupportedCommand::View
+                | SupportedCommand::Stats
+                | SupportedCommand::Export
+                | SupportedCommand::Import
+                | SupportedCommand::Migrate
+                | SupportedCommand::Backend
+                | SupportedCommand::Backup
+        )
+    }
+}
+
+impl std::fmt::Display for SupportedCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let command = match self {
+            SupportedCommand::Add => "add",
+            SupportedCommand::List => "list",
+            SupportedCommand::Search => "search",
+            SupportedCommand::Update => "update",
+            SupportedCommand::Delete => "delete",
+            SupportedCommand::Trash => "trash",
+            SupportedCommand::Restore => "restore",
+            SupportedCommand::EmptyTrash => "empty-trash",
+            SupportedCommand::Archive => "archive",
+            SupportedCommand::Archived => "archived",
+            SupportedCommand::Unarchive => "unarchive",
+            SupportedCommand::Undo => "undo",
+            SupportedCommand::Redo => "redo",
+            SupportedCommand::History => "history",
+            SupportedCommand::AsOf => "as-of",
+            SupportedCommand::Tui => "tui",
+            SupportedCommand::Board => "board",
+            SupportedCommand::Calendar => "calendar",
+            SupportedCommand::Agenda => "agenda",
+            SupportedCommand::View => "view",
+            SupportedCommand::Stats => "stats",
+            SupportedCommand::Export => "export",
+            SupportedCommand::Import => "import",
+            SupportedCommand::Migrate => "migrate",
+            SupportedCommand::Backend => "backend",
+            SupportedCommand::Backup => "backup",
+            SupportedCommand::Config => "config",
+            SupportedCommand::Help => "help",
+            SupportedCommand::Clear => "clear",
+            SupportedCommand::Exit => "exit",
+        };
+
+        write!(f, "{}", command)
     }
 }
 
@@ -31,8 +139,31 @@ impl FromStr for SupportedCommand {
         match input {
             "add" => Ok(SupportedCommand::Add),
             "list" => Ok(SupportedCommand::List),
+            "search" => Ok(SupportedCommand::Search),
             "update" => Ok(SupportedCommand::Update),
             "delete" => Ok(SupportedCommand::Delete),
+            "trash" => Ok(SupportedCommand::Trash),
+            "restore" => Ok(SupportedCommand::Restore),
+            "empty-trash" => Ok(SupportedCommand::EmptyTrash),
+            "archive" => Ok(SupportedCommand::Archive),
+            "archived" => Ok(SupportedCommand::Archived),
+            "unarchive" => Ok(SupportedCommand::Unarchive),
+            "undo" => Ok(SupportedCommand::Undo),
+            "redo" => Ok(SupportedCommand::Redo),
+            "history" => Ok(SupportedCommand::History),
+            "as-of" => Ok(SupportedCommand::AsOf),
+            "tui" => Ok(SupportedCommand::Tui),
+            "board" => Ok(SupportedCommand::Board),
+            "calendar" => Ok(SupportedCommand::Calendar),
+            "agenda" => Ok(SupportedCommand::Agenda),
+            "view" => Ok(SupportedCommand::View),
+            "stats" => Ok(SupportedCommand::Stats),
+            "export" => Ok(SupportedCommand::Export),
+            "import" => Ok(SupportedCommand::Import),
+            "migrate" => Ok(SupportedCommand::Migrate),
+            "backend" => Ok(SupportedCommand::Backend),
+            "backup" => Ok(SupportedCommand::Backup),
+            "config" => Ok(SupportedCommand::Config),
             "help" => Ok(SupportedCommand::Help),
             "clear" => Ok(SupportedCommand::Clear),
             "exit" => Ok(SupportedCommand::Exit),
@@ -0,0 +1,417 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Weekday};
+
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::priority::Priority;
+use crate::query::Filter;
+use crate::task::{parse_tags, Task};
+use crate::task_status::TaskStatus;
+
+// Tasks due on a day without a time are due at the end of it
+const END_OF_DAY: (u32, u32) = (23, 59);
+// Shown in prompts for due dates, see parse_due_date
+pub const DUE_DATE_FORMATS: &str = "dd.mm.YYYY HH:MM, dd.mm.YYYY, today, tomorrow or a weekday";
+// Guards against typos like 1-100000000 creating huge lists of ids
+const MAX_RANGE_LENGTH: usize = 10_000;
+
+// Splits a line into words like a shell does, text in quotes stays a single word
+pub fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+
+    if quote.is_some() {
+        return Err("Missing closing quote".to_string());
+    }
+    if in_word {
+        words.push(word);
+    }
+
+    Ok(words)
+}
+
+// Joins words back into a line that split_words turns into the same words
+pub fn join_words(words: &[String]) -> String {
+    words
+        .iter()
+        .map(|word| {
+            if word.is_empty() || word.contains(char::is_whitespace) {
+                format!("\"{}\"", word)
+            } else {
+                word.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Task fields given on the command line, e.g. `"Fix login" due:fri +auth`
+#[derive(Debug, Default)]
+pub struct TaskArguments {
+    // Everything that isn't a field or a tag, like the title or task ids
+    pub words: Vec<String>,
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub due_date: Option<NaiveDateTimeWrapper>,
+    // Moves existing due dates, e.g. due:+2d
+    pub due_date_shift: Option<Duration>,
+    pub status: Option<TaskStatus>,
+    // Some(None) removes the priority
+    pub priority: Option<Option<Priority>>,
+    pub added_tags: Vec<String>,
+    pub removed_tags: Vec<String>,
+}
+
+impl TaskArguments {
+    pub fn parse(arguments: &[String]) -> Result<TaskArguments, String> {
+        let mut parsed = TaskArguments::default();
+
+        for argument in arguments {
+            if let Some(tag) = argument.strip_prefix('+').filter(|tag| !tag.is_empty()) {
+                parsed.added_tags.extend(parse_tags(tag));
+                continue;
+            }
+            if let Some(tag) = argument.strip_prefix('-').filter(|tag| !tag.is_empty()) {
+                parsed.removed_tags.extend(parse_tags(tag));
+                continue;
+            }
+
+            let (field, value) = match argument.split_once(':') {
+                Some((field, value)) => (field.to_lowercase(), value),
+                None => {
+                    parsed.words.push(argument.clone());
+                    continue;
+                }
+            };
+
+            match field.as_str() {
+                "title" => parsed.title = Some(value.to_string()),
+                "description" | "desc" => parsed.description = Some(value.to_string()),
+                "due" if value.starts_with(['+', '-']) => {
+                    parsed.due_date_shift = Some(parse_shift(value)?)
+                }
+                "due" => parsed.due_date = Some(parse_due_date(value)?),
+                "status" => parsed.status = Some(parse_status(value)?),
+                "priority" => parsed.priority = Some(parse_priority(value)?),
+                // Not a field, e.g. a title like "Meeting: budget"
+                _ => parsed.words.push(argument.clone()),
+            }
+        }
+
+        Ok(parsed)
+    }
+
+    pub fn has_changes(&self) -> bool {
+        self.title.is_some()
+            || self.description.is_some()
+            || self.due_date.is_some()
+            || self.due_date_shift.is_some()
+            || self.status.is_some()
+            || self.priority.is_some()
+            || !self.added_tags.is_empty()
+            || !self.removed_tags.is_empty()
+    }
+
+    // Due date of a task after the change, tasks without one can't be shifted
+    pub fn apply_due_date(
+        &self,
+        due_date: Option<NaiveDateTimeWrapper>,
+    ) -> Result<Option<NaiveDateTimeWrapper>, String> {
+        match (self.due_date, self.due_date_shift, due_date) {
+            (Some(new_due_date), _, _) => Ok(Some(new_due_date)),
+            (None, Some(shift), Some(due_date)) => shift_due_date(due_date, shift).map(Some),
+            (None, _, due_date) => Ok(due_date),
+        }
+    }
+
+    // The task as it will look after the change, used to check and preview updates
+    pub fn apply_to(&self, task: &Task) -> Result<Task, String> {
+        let mut task = task.clone();
+
+        if let Some(title) = &self.title {
+            task.title = title.clone();
+        }
+        if let Some(description) = &self.description {
+            task.set_description(description.clone());
+        }
+        task.due_date = self.apply_due_date(task.due_date)?;
+        if let Some(status) = &self.status {
+            task.set_status(status.clone());
+        }
+        if let Some(priority) = self.priority {
+            task.set_priority(priority);
+        }
+        task.set_tags(self.apply_tags(&task.tags));
+
+        Ok(task)
+    }
+
+    // Tags of a task after adding and removing the tags given on the command line
+    pub fn apply_tags(&self, tags: &[String]) -> Vec<String> {
+        let mut tags: Vec<String> = tags
+            .iter()
+            .filter(|tag| !self.removed_tags.iter().any(|r| r.eq_ignore_ascii_case(tag)))
+            .cloned()
+            .collect();
+
+        for tag in &self.added_tags {
+            if !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                tags.push(tag.clone());
+            }
+        }
+
+        tags
+    }
+}
+
+// Tasks chosen by ids and ranges like `3,5,8-12`, or by a query like
+// `status:completed tag:old`
+#[derive(Debug)]
+pub enum TaskSelector {
+    Ids(Vec<usize>),
+    Filter(Filter),
+}
+
+impl TaskSelector {
+    pub fn parse(words: &[String], views: &BTreeMap<String, String>) -> Result<TaskSelector, String> {
+        if words.iter().all(|word| is_task_id_list(word)) {
+            return Ok(TaskSelector::Ids(parse_task_ids(words)?));
+        }
+
+        Ok(TaskSelector::Filter(Filter::parse(words, views)?))
+    }
+
+    pub fn is_filter(&self) -> bool {
+        matches!(self, TaskSelector::Filter(_))
+    }
+
+    fn matches(&self, task: &Task) -> bool {
+        match self {
+            TaskSelector::Ids(task_ids) => task_ids.contains(&task.id),
+            TaskSelector::Filter(filter) => filter.matches(task),
+        }
+    }
+
+    // The selected tasks out of tasks, ids that don't match any task are returned separately
+    pub fn select<'a>(&self, tasks: impl Iterator<Item = &'a Task>) -> (Vec<&'a Task>, Vec<usize>) {
+        let selected: Vec<&Task> = tasks.filter(|task| self.matches(task)).collect();
+
+        let missing = match self {
+            TaskSelector::Ids(task_ids) => task_ids
+                .iter()
+                .filter(|id| !selected.iter().any(|task| task.id == **id))
+                .copied()
+                .collect(),
+            TaskSelector::Filter(_) => Vec::new(),
+        };
+
+        (selected, missing)
+    }
+}
+
+pub fn is_task_id_list(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit())
+        && word.chars().all(|c| c.is_ascii_digit() || c == ',' || c == '-')
+}
+
+// Parses ids given as separate words, comma separated lists and ranges like 8-12
+pub fn parse_task_ids(words: &[String]) -> Result<Vec<usize>, String> {
+    let mut task_ids = Vec::new();
+
+    for part in words.iter().flat_map(|word| word.split(',')).filter(|part| !part.is_empty()) {
+        let invalid = || format!("Invalid task Id: {}", part);
+
+        let (start, end) = match part.split_once('-') {
+            Some((start, end)) => (
+                start.parse::<usize>().map_err(|_| invalid())?,
+                end.parse::<usize>().map_err(|_| invalid())?,
+            ),
+            None => {
+                let task_id = part.parse::<usize>().map_err(|_| invalid())?;
+                (task_id, task_id)
+            }
+        };
+
+        if start > end || end - start >= MAX_RANGE_LENGTH {
+            return Err(format!("Invalid range of task Ids: {}", part));
+        }
+        for task_id in start..=end {
+            if !task_ids.contains(&task_id) {
+                task_ids.push(task_id);
+            }
+        }
+    }
+
+    if task_ids.is_empty() {
+        return Err("No task Ids given".to_string());
+    }
+
+    Ok(task_ids)
+}
+
+// Parses shifts like +2d, -1w or +3h
+pub fn parse_shift(value: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid due date shift: {} (use e.g. +2d, -1w or +3h)", value);
+
+    let (sign, rest) = value.split_at(1);
+    let unit = rest.chars().last().ok_or_else(invalid)?;
+    let amount: i64 = rest[..rest.len() - unit.len_utf8()].parse().map_err(|_| invalid())?;
+    let amount = if sign == "-" { -amount } else { amount };
+
+    let unit_milliseconds: i64 = match unit.to_ascii_lowercase() {
+        'h' => 60 * 60 * 1000,
+        'd' => 24 * 60 * 60 * 1000,
+        'w' => 7 * 24 * 60 * 60 * 1000,
+        _ => return Err(invalid()),
+    };
+    // Duration::days and the like panic on amounts that don't fit
+    amount
+        .checked_mul(unit_milliseconds)
+        .map(Duration::milliseconds)
+        .ok_or_else(invalid)
+}
+
+// Moves a due date by a shift from parse_shift, unless that leaves the dates chrono can represent
+pub fn shift_due_date(due_date: NaiveDateTimeWrapper, shift: Duration) -> Result<NaiveDateTimeWrapper, String> {
+    due_date
+        .0
+        .checked_add_signed(shift)
+        .map(NaiveDateTimeWrapper::new)
+        .ok_or_else(|| format!("Invalid due date shift: it moves {} past the supported dates", due_date))
+}
+
+// Accepts low, medium, high or none
+pub fn parse_priority(value: &str) -> Result<Option<Priority>, String> {
+    if value.eq_ignore_ascii_case("none") {
+        return Ok(None);
+    }
+
+    Priority::from_str(value)
+        .map(Some)
+        .map_err(|_| format!("Invalid priority: {} (use low, medium, high or none)", value))
+}
+
+// Accepts statuses written as inprogress, in-progress, in_progress or "in progress"
+pub fn parse_status(value: &str) -> Result<TaskStatus, String> {
+    let letters = |value: &str| value.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+
+    TaskStatus::all()
+        .into_iter()
+        .find(|status| letters(&status.to_string()) == letters(value))
+        .ok_or_else(|| format!("Invalid status: {} (use not started, in progress or completed)", value))
+}
+
+// Accepts today, tomorrow, a weekday (the next one, or today), dd.mm.YYYY and
+// dd.mm.YYYY HH:MM. Used wherever a due date is entered.
+pub fn parse_due_date(value: &str) -> Result<NaiveDateTimeWrapper, String> {
+    let value = value.trim();
+
+    if let Ok(due_date) = NaiveDateTimeWrapper::parse_from_str(&format!("{}:00", value), "%d.%m.%Y %H:%M:%S") {
+        return Ok(due_date);
+    }
+
+    let today = NaiveDateTimeWrapper::now().0.date();
+    let date = match value.to_lowercase().as_str() {
+        "today" => Some(today),
+        "tomorrow" => Some(today + Duration::days(1)),
+        day => match day.parse::<Weekday>() {
+            Ok(weekday) => {
+                let days_ahead = (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7;
+                Some(today + Duration::days(days_ahead.into()))
+            }
+            Err(_) => NaiveDate::parse_from_str(day, "%d.%m.%Y").ok(),
+        },
+    };
+
+    let (hour, minute) = END_OF_DAY;
+    match date.zip(NaiveTime::from_hms_opt(hour, minute, 0)) {
+        Some((date, time)) => Ok(NaiveDateTimeWrapper::new(date.and_time(time))),
+        None => Err(format!("Invalid due date: {}", value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        split_words(line).unwrap()
+    }
+
+    #[test]
+    fn parses_statuses_written_in_any_form() {
+        for value in ["inprogress", "in-progress", "in_progress", "In Progress", "\"in progress\""] {
+            assert_eq!(parse_status(value), Ok(TaskStatus::InProgress), "{}", value);
+        }
+        assert_eq!(parse_status("not-started"), Ok(TaskStatus::NotStarted));
+        assert_eq!(parse_status("COMPLETED"), Ok(TaskStatus::Completed));
+        assert!(parse_status("done").is_err());
+        assert!(parse_status("").is_err());
+    }
+
+    #[test]
+    fn parses_due_dates() {
+        let due_date = |value| parse_due_date(value).map(|due_date| due_date.0.to_string());
+
+        assert_eq!(due_date("24.12.2025 18:30"), Ok("2025-12-24 18:30:00".to_string()));
+        // Dates without a time are due at the end of the day
+        assert_eq!(due_date(" 24.12.2025 "), Ok("2025-12-24 23:59:00".to_string()));
+        assert!(due_date("31.02.2025").is_err());
+        assert!(due_date("2025-12-24").is_err());
+        assert!(due_date("someday").is_err());
+
+        let today = NaiveDateTimeWrapper::now().0.date();
+        assert_eq!(parse_due_date("Today").unwrap().0.date(), today);
+        assert_eq!(parse_due_date("tomorrow").unwrap().0.date(), today + Duration::days(1));
+        let friday = parse_due_date("fri").unwrap().0.date();
+        assert_eq!(friday.weekday(), Weekday::Fri);
+        assert!(friday >= today && friday < today + Duration::days(7));
+    }
+
+    #[test]
+    fn parses_task_arguments() {
+        let arguments = TaskArguments::parse(&words(
+            "\"Fix login\" desc:\"Users get logged out\" due:24.12.2025 status:inprogress priority:high +auth +api -old",
+        ))
+        .unwrap();
+
+        assert_eq!(arguments.words, vec!["Fix login".to_string()]);
+        assert_eq!(arguments.description.as_deref(), Some("Users get logged out"));
+        assert_eq!(arguments.due_date.map(|d| d.0.to_string()), Some("2025-12-24 23:59:00".to_string()));
+        assert_eq!(arguments.status, Some(TaskStatus::InProgress));
+        assert_eq!(arguments.priority, Some(Some(Priority::High)));
+        assert_eq!(arguments.added_tags, vec!["auth".to_string(), "api".to_string()]);
+        assert_eq!(arguments.removed_tags, vec!["old".to_string()]);
+
+        let shift = TaskArguments::parse(&words("due:-1w priority:none")).unwrap();
+        assert_eq!(shift.due_date_shift, Some(Duration::weeks(-1)));
+        assert_eq!(shift.priority, Some(None));
+
+        assert!(TaskArguments::parse(&words("status:later")).is_err());
+        assert!(TaskArguments::parse(&words("due:+99999999999999w")).is_err());
+    }
+}
@@ -1,13 +1,38 @@
+use std::collections::hash_map::RandomState;
+use std::collections::BTreeMap;
+use std::hash::{BuildHasher, Hasher};
 use std::io::{Read, Write};
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::backup::DEFAULT_BACKUP_RETENTION;
 use crate::supported_persistence::SupportedPersistence;
+use crate::table::{Column, ListLayout};
+
+pub const CONFIG_FILE: &str = "config.bin";
+// Version of the config.bin format, written after CONFIG_MAGIC. Files without it
+// were written before versions were recorded and are version 1.
+pub const CONFIG_VERSION: u32 = 3;
+const CONFIG_MAGIC: &[u8; 4] = b"RTDC";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Configuration {
     pub persistence: SupportedPersistence,
     pub task_counter: usize,
+    // Tasks in trash are purged automatically after this many days
+    pub trash_retention_days: Option<u32>,
+    // Completed tasks are archived automatically after this many days
+    pub auto_archive_days: Option<u32>,
+    pub list_layout: ListLayout,
+    pub list_columns: Vec<Column>,
+    // Wrap long titles and descriptions in the table instead of truncating them
+    pub wrap_list_values: bool,
+    // Named queries used with list @name
+    pub views: BTreeMap<String, String>,
+    // Written into exported files so importing them only matches tasks of this list
+    pub list_id: u32,
+    // Number of backups of the tasks kept, none are made with 0
+    pub backup_retention: usize,
 }
 
 impl Configuration {
@@ -15,24 +40,48 @@ impl Configuration {
         Configuration {
             persistence,
             task_counter,
+            trash_retention_days: None,
+            auto_archive_days: None,
+            list_layout: ListLayout::Table,
+            list_columns: Column::default_columns(),
+            wrap_list_values: false,
+            views: BTreeMap::new(),
+            list_id: new_list_id(),
+            backup_retention: DEFAULT_BACKUP_RETENTION,
         }
     }
 
-    pub fn load_or_create_configuration(name: &str) -> Self {
+    // A file that can't be decoded is left alone and returned as an error, as
+    // replacing it would lose the settings and the task counter
+    pub fn load_or_create_configuration(name: &str) -> Result<Self, String> {
         let file = std::fs::File::open(name);
         match file {
             Ok(mut file) => {
                 let mut encoded_config = Vec::new();
-                file.read_to_end(&mut encoded_config).unwrap();
-                let config: Configuration = bincode::deserialize(&encoded_config).unwrap();
-                println!("Loaded configuration: {:?}", config);
-                return config;
+                file.read_to_end(&mut encoded_config).map_err(|e| format!("{} could not be read: {}", name, e))?;
+                let (version, payload) = split_version(&encoded_config);
+                if version != CONFIG_VERSION {
+                    return Err(format!(
+                        "{} has version {}, expected version {}. Run migrate to upgrade it",
+                        name, version, CONFIG_VERSION
+                    ));
+                }
+                match bincode::deserialize::<Configuration>(payload) {
+                    Ok(config) => {
+                        println!("Loaded configuration: {:?}", config);
+                        Ok(config)
+                    }
+                    Err(e) => Err(format!(
+                        "{} could not be decoded ({}). It was left as it is, move it away to start with default settings",
+                        name, e
+                    )),
+                }
             }
             Err(_) => {
                 println!("No configuration file found. Creating a new one.");
                 let config = Configuration::new(SupportedPersistence::CSV, 0);
                 config.save_configuration(name);
-                return config;
+                Ok(config)
             }
         }
     }
@@ -40,7 +89,30 @@ impl Configuration {
     pub fn save_configuration(&self, name: &str) {
         let mut file = std::fs::File::create(name).unwrap();
         let encoded_config = bincode::serialize(&self).unwrap();
-        file.write_all(&encoded_config).unwrap();
+        file.write_all(&join_version(CONFIG_VERSION, &encoded_config)).unwrap();
         file.flush().unwrap();
     }
 }
+
+// Random, so lists on different machines or in different directories get different ids
+pub fn new_list_id() -> u32 {
+    RandomState::new().build_hasher().finish() as u32
+}
+
+// The format version of an encoded configuration and the encoded fields after it
+pub fn split_version(encoded_config: &[u8]) -> (u32, &[u8]) {
+    match encoded_config.strip_prefix(CONFIG_MAGIC) {
+        Some(rest) if rest.len() >= 4 => {
+            let (version, payload) = rest.split_at(4);
+            (u32::from_le_bytes([version[0], version[1], version[2], version[3]]), payload)
+        }
+        _ => (1, encoded_config),
+    }
+}
+
+pub fn join_version(version: u32, payload: &[u8]) -> Vec<u8> {
+    let mut encoded_config = CONFIG_MAGIC.to_vec();
+    encoded_config.extend_from_slice(&version.to_le_bytes());
+    encoded_config.extend_from_slice(payload);
+    encoded_config
+}
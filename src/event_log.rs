@@ -0,0 +1,608 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::history::Change;
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::persistence;
+use crate::priority::Priority;
+use crate::task::{parse_tags, Task};
+use crate::task_status::TaskStatus;
+
+pub const EVENT_LOG_FILE: &str = "events.log";
+pub const SNAPSHOT_DIRECTORY: &str = "snapshots";
+// A snapshot of all tasks is written every time this many events were appended
+pub const SNAPSHOT_INTERVAL: usize = 500;
+
+const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d%H%M%S";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EventKind {
+    Created,
+    Changed,
+    Deleted,
+    Archived,
+    Unarchived,
+}
+
+// Written the same as in events.log
+impl std::fmt::Display for EventKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl FromStr for EventKind {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<EventKind, Self::Err> {
+        match input {
+            "Created" => Ok(EventKind::Created),
+            "Changed" => Ok(EventKind::Changed),
+            "Deleted" => Ok(EventKind::Deleted),
+            "Archived" => Ok(EventKind::Archived),
+            "Unarchived" => Ok(EventKind::Unarchived),
+            _ => Err(()),
+        }
+    }
+}
+
+// Events are only ever appended to the log, replaying them on top of a
+// snapshot gives the tasks as they were at any point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Event {
+    pub timestamp: NaiveDateTimeWrapper,
+    pub task_id: usize,
+    pub kind: EventKind,
+    pub field: Option<String>,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl Event {
+    fn new(timestamp: NaiveDateTimeWrapper, task_id: usize, kind: EventKind) -> Event {
+        Event {
+            timestamp,
+            task_id,
+            kind,
+            field: None,
+            old_value: None,
+            new_value: None,
+        }
+    }
+
+    fn changed(
+        timestamp: NaiveDateTimeWrapper,
+        task_id: usize,
+        field: &str,
+        old_value: Option<String>,
+        new_value: Option<String>,
+    ) -> Event {
+        Event {
+            timestamp,
+            task_id,
+            kind: EventKind::Changed,
+            field: Some(field.to_string()),
+            old_value,
+            new_value,
+        }
+    }
+
+    pub fn from_change(change: &Change, timestamp: NaiveDateTimeWrapper) -> Vec<Event> {
+        match change {
+            Change::Added { task, .. } => {
+                let mut created = Event::new(timestamp, task.id, EventKind::Created);
+                created.new_value = Some(task.title.clone());
+
+                // Anything set on top of a new task is logged as a change from its default
+                let mut events = vec![created];
+                // Replaying the Created event sets the creation time to its
+                // timestamp, a different creation time is logged as a change
+                let mut new_task = Task::new(task.id, task.title.clone());
+                new_task.set_created_at(Some(timestamp));
+                events.extend(Event::from_change(
+                    &Change::Updated {
+                        before: new_task,
+                        after: task.clone(),
+                    },
+                    timestamp,
+                ));
+                events
+            }
+            Change::Updated { before, after } => field_values(before)
+                .into_iter()
+                .zip(field_values(after))
+                .filter(|((_, old_value), (_, new_value))| old_value != new_value)
+                .map(|((field, old_value), (_, new_value))| {
+                    Event::changed(timestamp, after.id, field, old_value, new_value)
+                })
+                .collect(),
+            Change::Deleted { task, .. } => vec![Event::new(timestamp, task.id, EventKind::Deleted)],
+            Change::Archived { task, .. } => vec![Event::new(timestamp, task.id, EventKind::Archived)],
+            Change::Unarchived { task, .. } => {
+                vec![Event::new(timestamp, task.id, EventKind::Unarchived)]
+            }
+        }
+    }
+
+    fn apply(&self, tasks: &mut Vec<Task>, archived_tasks: &mut Vec<Task>) {
+        match self.kind {
+            EventKind::Created => {
+                let title = self.new_value.clone().unwrap_or_default();
+                let mut task = Task::new(self.task_id, title);
+                task.set_created_at(Some(self.timestamp));
+                tasks.retain(|t| t.id != self.task_id);
+                tasks.push(task);
+            }
+            EventKind::Changed => {
+                let task = tasks
+                    .iter_mut()
+                    .chain(archived_tasks.iter_mut())
+                    .find(|t| t.id == self.task_id);
+
+                if let (Some(task), Some(field)) = (task, &self.field) {
+                    set_field(task, field, self.new_value.as_deref());
+                }
+            }
+            EventKind::Deleted => {
+                tasks.retain(|t| t.id != self.task_id);
+                archived_tasks.retain(|t| t.id != self.task_id);
+            }
+            EventKind::Archived => move_task(self.task_id, tasks, archived_tasks),
+            EventKind::Unarchived => move_task(self.task_id, archived_tasks, tasks),
+        }
+    }
+}
+
+impl std::fmt::Display for Event {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let old_value = self.old_value.as_deref().unwrap_or("(none)");
+        let new_value = self.new_value.as_deref().unwrap_or("(none)");
+
+        match (&self.kind, self.field.as_deref()) {
+            (EventKind::Created, _) => write!(f, "created as '{}'", new_value),
+            (EventKind::Changed, Some("deleted_at")) if self.new_value.is_some() => {
+                write!(f, "moved to trash")
+            }
+            (EventKind::Changed, Some("deleted_at")) => write!(f, "restored from trash"),
+            (EventKind::Changed, Some("status")) => {
+                write!(f, "status changed from {} to {}", old_value, new_value)
+            }
+            (EventKind::Changed, field) => write!(
+                f,
+                "{} changed from '{}' to '{}'",
+                field.unwrap_or("unknown field"),
+                old_value,
+                new_value
+            ),
+            (EventKind::Deleted, _) => write!(f, "deleted permanently"),
+            (EventKind::Archived, _) => write!(f, "archived"),
+            (EventKind::Unarchived, _) => write!(f, "unarchived"),
+        }
+    }
+}
+
+fn field_values(task: &Task) -> Vec<(&'static str, Option<String>)> {
+    vec![
+        ("title", Some(task.title.clone())),
+        ("description", task.description.clone()),
+        ("due_date", task.due_date.map(|d| d.to_string())),
+        ("status", Some(task.status.to_string())),
+        ("deleted_at", task.deleted_at.map(|d| d.to_string())),
+        ("completed_at", task.completed_at.map(|d| d.to_string())),
+        ("tags", Some(task.tags.join(" ")).filter(|tags| !tags.is_empty())),
+        ("priority", task.priority.map(|p| p.to_string())),
+        ("created_at", task.created_at.map(|d| d.to_string())),
+        ("uid", task.uid.clone()),
+    ]
+}
+
+fn set_field(task: &mut Task, field: &str, value: Option<&str>) {
+    let date_value = value.and_then(|v| NaiveDateTimeWrapper::from_str(v).ok());
+
+    match field {
+        "title" => task.title = value.unwrap_or_default().to_string(),
+        "description" => task.description = value.map(|v| v.to_string()),
+        "due_date" => task.due_date = date_value,
+        "status" => {
+            let status = value.and_then(|v| TaskStatus::from_str(v).ok());
+            task.set_status(status.unwrap_or(TaskStatus::NotStarted));
+        }
+        "deleted_at" => task.set_deleted_at(date_value),
+        "completed_at" => task.set_completed_at(date_value),
+        "tags" => task.set_tags(value.map(parse_tags).unwrap_or_default()),
+        "priority" => task.set_priority(value.and_then(|v| Priority::from_str(v).ok())),
+        "created_at" => task.set_created_at(date_value),
+        "uid" => task.set_uid(value.map(|v| v.to_string())),
+        _ => {}
+    }
+}
+
+fn move_task(task_id: usize, from: &mut Vec<Task>, to: &mut Vec<Task>) {
+    if let Some(index) = from.iter().position(|t| t.id == task_id) {
+        let task = from.remove(index);
+        to.push(task);
+    }
+}
+
+pub fn exists() -> bool {
+    Path::new(EVENT_LOG_FILE).exists()
+}
+
+// Starts the log with a snapshot of the current tasks, as older changes were never logged
+pub fn initialize(tasks: &[Task], archived_tasks: &[Task]) -> Result<(), Box<dyn Error>> {
+    if exists() {
+        return Ok(());
+    }
+
+    std::fs::File::create(EVENT_LOG_FILE)?;
+    write_snapshot(0, tasks, archived_tasks)
+}
+
+// Events that turn the tasks before into the tasks after, for changes that
+// weren't recorded as they were made
+pub fn events_between(
+    (tasks_before, archived_before): (&[Task], &[Task]),
+    (tasks_after, archived_after): (&[Task], &[Task]),
+    timestamp: NaiveDateTimeWrapper,
+) -> Vec<Event> {
+    let find = |tasks: &[Task], archived_tasks: &[Task], task_id: usize| {
+        let task = tasks.iter().find(|t| t.id == task_id).map(|t| (t.clone(), false));
+        task.or_else(|| archived_tasks.iter().find(|t| t.id == task_id).map(|t| (t.clone(), true)))
+    };
+
+    let mut task_ids: Vec<usize> = tasks_before
+        .iter()
+        .chain(archived_before)
+        .chain(tasks_after)
+        .chain(archived_after)
+        .map(|t| t.id)
+        .collect();
+    task_ids.sort();
+    task_ids.dedup();
+
+    let mut changes = Vec::new();
+    for task_id in task_ids {
+        match (find(tasks_before, archived_before, task_id), find(tasks_after, archived_after, task_id)) {
+            (Some((task, _)), None) => changes.push(Change::Deleted { index: 0, task }),
+            (None, Some((task, archived))) => {
+                changes.push(Change::Added { index: 0, task: task.clone() });
+                if archived {
+                    changes.push(Change::Archived { index: 0, task });
+                }
+            }
+            (Some((before, was_archived)), Some((after, archived))) => {
+                if was_archived && !archived {
+                    changes.push(Change::Unarchived { index: 0, task: before.clone() });
+                }
+                if before != after {
+                    changes.push(Change::Updated { before, after: after.clone() });
+                }
+                if !was_archived && archived {
+                    changes.push(Change::Archived { index: 0, task: after });
+                }
+            }
+            (None, None) => {}
+        }
+    }
+
+    changes.iter().flat_map(|change| Event::from_change(change, timestamp)).collect()
+}
+
+// Appends events to a log that already holds logged_events events, and writes a
+// snapshot of tasks (which must already include the events) whenever another
+// SNAPSHOT_INTERVAL events were logged. Returns the number of events logged now.
+pub fn append(
+    events: &[Event],
+    logged_events: usize,
+    tasks: &[Task],
+    archived_tasks: &[Task],
+) -> Result<usize, Box<dyn Error>> {
+    if events.is_empty() {
+        return Ok(logged_events);
+    }
+
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(EVENT_LOG_FILE)?;
+    let write_header = file.metadata()?.len() == 0;
+    let mut writer = csv::WriterBuilder::new()
+        .has_headers(write_header)
+        .from_writer(file);
+
+    for event in events {
+        writer.serialize(event)?;
+    }
+    writer.flush()?;
+
+    let total_events = logged_events + events.len();
+    if snapshot_due(logged_events, total_events) {
+        write_snapshot(total_events, tasks, archived_tasks)?;
+    }
+
+    Ok(total_events)
+}
+
+// Replaces the log with events copied from another backend. Their snapshots
+// aren't copied, so tasks can be listed as of dates after the copy only.
+pub fn write_events(events: &[Event], tasks: &[Task], archived_tasks: &[Task]) -> Result<(), Box<dyn Error>> {
+    let mut writer = csv::Writer::from_path(EVENT_LOG_FILE)?;
+    for event in events {
+        writer.serialize(event)?;
+    }
+    writer.flush()?;
+
+    write_snapshot(events.len(), tasks, archived_tasks)
+}
+
+pub fn load_events() -> Result<Vec<Event>, Box<dyn Error>> {
+    if !exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut reader = csv::Reader::from_path(EVENT_LOG_FILE)?;
+    let mut events = Vec::new();
+
+    for result in reader.deserialize() {
+        events.push(result?);
+    }
+
+    Ok(events)
+}
+
+// When each task was created, also known for tasks from before Task::created_at
+pub fn creation_times(events: Vec<Event>) -> HashMap<usize, NaiveDateTimeWrapper> {
+    events
+        .into_iter()
+        .filter(|e| e.kind == EventKind::Created)
+        .map(|e| (e.task_id, e.timestamp))
+        .collect()
+}
+
+pub fn task_history(events: Vec<Event>, task_id: usize) -> Vec<Event> {
+    events.into_iter().filter(|e| e.task_id == task_id).collect()
+}
+
+pub struct RebuiltTasks {
+    pub tasks: Vec<Task>,
+    pub archived_tasks: Vec<Task>,
+    pub logged_events: usize,
+}
+
+// The tasks of a snapshot, taken after sequence events were logged
+pub struct LoadedSnapshot {
+    pub sequence: usize,
+    pub tasks: Vec<Task>,
+    pub archived_tasks: Vec<Task>,
+}
+
+// Whether a snapshot is due after the log grew from logged_events to total_events
+pub fn snapshot_due(logged_events: usize, total_events: usize) -> bool {
+    logged_events / SNAPSHOT_INTERVAL != total_events / SNAPSHOT_INTERVAL
+}
+
+// Rebuilds tasks and archived tasks as they were at until, or as they are now,
+// by replaying the events after the snapshot
+pub fn replay_events(
+    events: &[Event],
+    snapshot: Option<LoadedSnapshot>,
+    until: Option<NaiveDateTime>,
+) -> Result<(Vec<Task>, Vec<Task>), Box<dyn Error>> {
+    let snapshot = match snapshot {
+        Some(snapshot) => snapshot,
+        None => return Err("no history was recorded before that date".into()),
+    };
+
+    let (mut tasks, mut archived_tasks) = (snapshot.tasks, snapshot.archived_tasks);
+    for event in events.iter().skip(snapshot.sequence) {
+        if until.is_none_or(|until| event.timestamp.0 <= until) {
+            event.apply(&mut tasks, &mut archived_tasks);
+        }
+    }
+
+    Ok((tasks, archived_tasks))
+}
+
+// The latest snapshot in the snapshot directory taken after at most
+// max_sequence events, and not after until if given
+pub fn load_snapshot(
+    max_sequence: usize,
+    until: Option<NaiveDateTime>,
+) -> Result<Option<LoadedSnapshot>, Box<dyn Error>> {
+    let snapshot = find_snapshots()?
+        .into_iter()
+        .filter(|snapshot| snapshot.sequence <= max_sequence)
+        .filter(|snapshot| until.is_none_or(|until| snapshot.time <= until))
+        .max_by_key(|snapshot| snapshot.sequence);
+
+    let snapshot = match snapshot {
+        Some(snapshot) => snapshot,
+        None => return Ok(None),
+    };
+
+    Ok(Some(LoadedSnapshot {
+        sequence: snapshot.sequence,
+        tasks: persistence::load_tasks_from_csv(&snapshot.tasks_path())?.tasks,
+        archived_tasks: persistence::load_tasks_from_csv(&snapshot.archive_path())?.tasks,
+    }))
+}
+
+struct Snapshot {
+    // Number of events logged when the snapshot was taken
+    sequence: usize,
+    time: NaiveDateTime,
+    name: String,
+}
+
+impl Snapshot {
+    fn tasks_path(&self) -> String {
+        format!("{}/{}.csv", SNAPSHOT_DIRECTORY, self.name)
+    }
+
+    fn archive_path(&self) -> String {
+        format!("{}/{}-archive.csv", SNAPSHOT_DIRECTORY, self.name)
+    }
+}
+
+fn write_snapshot(
+    sequence: usize,
+    tasks: &[Task],
+    archived_tasks: &[Task],
+) -> Result<(), Box<dyn Error>> {
+    std::fs::create_dir_all(SNAPSHOT_DIRECTORY)?;
+
+    let time = NaiveDateTimeWrapper::now().0;
+    let snapshot = Snapshot {
+        sequence,
+        time,
+        name: format!("{}-{}", sequence, time.format(SNAPSHOT_TIME_FORMAT)),
+    };
+
+    persistence::save_tasks_to_csv(&snapshot.tasks_path(), tasks)?;
+    persistence::save_tasks_to_csv(&snapshot.archive_path(), archived_tasks)
+}
+
+fn find_snapshots() -> Result<Vec<Snapshot>, Box<dyn Error>> {
+    let mut snapshots = Vec::new();
+
+    if !Path::new(SNAPSHOT_DIRECTORY).exists() {
+        return Ok(snapshots);
+    }
+
+    for entry in std::fs::read_dir(SNAPSHOT_DIRECTORY)? {
+        let file_name = entry?.file_name().to_string_lossy().to_string();
+        let name = match file_name.strip_suffix(".csv") {
+            Some(name) if !name.ends_with("-archive") => name.to_string(),
+            _ => continue,
+        };
+
+        let (sequence, time) = match name.split_once('-') {
+            Some((sequence, time)) => (
+                sequence.parse(),
+                NaiveDateTime::parse_from_str(time, SNAPSHOT_TIME_FORMAT),
+            ),
+            None => continue,
+        };
+
+        if let (Ok(sequence), Ok(time)) = (sequence, time) {
+            snapshots.push(Snapshot {
+                sequence,
+                time,
+                name,
+            });
+        }
+    }
+
+    Ok(snapshots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(time: &str) -> NaiveDateTimeWrapper {
+        NaiveDateTimeWrapper::from_str(time).unwrap()
+    }
+
+    fn task(id: usize, title: &str, created_at: &str) -> Task {
+        let mut task = Task::new(id, title.to_string());
+        task.set_created_at(Some(time(created_at)));
+        task
+    }
+
+    fn snapshot(sequence: usize, tasks: &[Task], archived_tasks: &[Task]) -> Option<LoadedSnapshot> {
+        Some(LoadedSnapshot {
+            sequence,
+            tasks: tasks.to_vec(),
+            archived_tasks: archived_tasks.to_vec(),
+        })
+    }
+
+    #[test]
+    fn replaying_logged_changes_gives_the_tasks() {
+        let mut report = task(1, "Write report", "2024-03-01 09:00:00");
+        report.set_description("For the board".to_string());
+        report.set_due_date(time("2024-03-08 17:00:00"));
+        report.set_status(TaskStatus::InProgress);
+        let mut archived = task(2, "Old task", "2024-02-01 09:00:00");
+        archived.set_status(TaskStatus::Completed);
+        archived.set_completed_at(Some(time("2024-02-02 10:00:00")));
+        let tasks = vec![report];
+        let archived_tasks = vec![archived];
+
+        let events = events_between((&[], &[]), (&tasks, &archived_tasks), time("2024-03-01 09:00:00"));
+        let replayed = replay_events(&events, snapshot(0, &[], &[]), None).unwrap();
+
+        assert_eq!(replayed, (tasks, archived_tasks));
+        assert_eq!(events[0].kind, EventKind::Created);
+        assert_eq!(events.last().map(|e| &e.kind), Some(&EventKind::Archived));
+    }
+
+    #[test]
+    fn replays_changes_up_to_a_date() {
+        let before = vec![task(1, "Draft", "2024-03-01 09:00:00"), task(2, "Call back", "2024-03-01 09:00:00")];
+        let mut after = before.clone();
+        after[0].title = "Final".to_string();
+        after.remove(1);
+
+        let mut events = events_between((&[], &[]), (&before, &[]), time("2024-03-01 09:00:00"));
+        events.extend(events_between((&before, &[]), (&after, &[]), time("2024-03-05 12:00:00")));
+
+        let (tasks, _) = replay_events(&events, snapshot(0, &[], &[]), None).unwrap();
+        assert_eq!(tasks, after);
+
+        let until = time("2024-03-02 00:00:00").0;
+        let (tasks, _) = replay_events(&events, snapshot(0, &[], &[]), Some(until)).unwrap();
+        assert_eq!(tasks, before);
+        assert_eq!(events.last().unwrap().to_string(), "deleted permanently");
+    }
+
+    #[test]
+    fn replays_only_events_after_the_snapshot() {
+        let tasks = vec![task(1, "One", "2024-03-01 09:00:00")];
+        let mut changed = tasks.clone();
+        changed[0].title = "One changed".to_string();
+
+        let mut events = events_between((&[], &[]), (&tasks, &[]), time("2024-03-01 09:00:00"));
+        let sequence = events.len();
+        events.extend(events_between((&tasks, &[]), (&changed, &[]), time("2024-03-02 09:00:00")));
+        assert_eq!(events[sequence].to_string(), "title changed from 'One' to 'One changed'");
+
+        // Only the snapshot has the description, replaying the Created event
+        // before it would have removed it
+        let mut snapshot_tasks = tasks.clone();
+        snapshot_tasks[0].description = Some("Only in the snapshot".to_string());
+        let (replayed, _) = replay_events(&events, snapshot(sequence, &snapshot_tasks, &[]), None).unwrap();
+
+        assert_eq!(replayed[0].title, "One changed");
+        assert_eq!(replayed[0].description.as_deref(), Some("Only in the snapshot"));
+        assert!(replay_events(&events, None, None).is_err());
+    }
+
+    #[test]
+    fn logs_moves_to_and_from_the_archive() {
+        let tasks = vec![task(1, "One", "2024-03-01 09:00:00")];
+
+        let archived = events_between((&tasks, &[]), (&[], &tasks), time("2024-03-02 09:00:00"));
+        let unarchived = events_between((&[], &tasks), (&tasks, &[]), time("2024-03-03 09:00:00"));
+
+        assert_eq!(archived.iter().map(|e| &e.kind).collect::<Vec<_>>(), vec![&EventKind::Archived]);
+        assert_eq!(unarchived.iter().map(|e| &e.kind).collect::<Vec<_>>(), vec![&EventKind::Unarchived]);
+        let replayed = replay_events(&archived, snapshot(0, &tasks, &[]), None).unwrap();
+        assert_eq!(replayed, (Vec::new(), tasks));
+    }
+
+    #[test]
+    fn takes_a_snapshot_every_interval() {
+        assert!(!snapshot_due(0, SNAPSHOT_INTERVAL - 1));
+        assert!(snapshot_due(SNAPSHOT_INTERVAL - 1, SNAPSHOT_INTERVAL));
+        assert!(snapshot_due(10, SNAPSHOT_INTERVAL * 2 + 3));
+        assert!(!snapshot_due(SNAPSHOT_INTERVAL, SNAPSHOT_INTERVAL + 10));
+    }
+}
@@ -0,0 +1,135 @@
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::backup::BACKUP_DIRECTORY;
+use crate::configuration::CONFIG_FILE;
+use crate::event_log::{EVENT_LOG_FILE, SNAPSHOT_DIRECTORY};
+use crate::history::HISTORY_FILE;
+use crate::html_report;
+use crate::ical;
+use crate::markdown::{self, Grouping};
+use crate::line_editor::COMMAND_HISTORY_FILE;
+use crate::persistence::{ARCHIVE_FILE, TASKS_FILE, TASKS_LOCK_FILE};
+use crate::task::Task;
+use crate::taskwarrior;
+use crate::todo_txt;
+
+// Files and directories the app keeps its own data in, never exported to
+const APP_FILES: [&str; 7] = [
+    TASKS_FILE,
+    TASKS_LOCK_FILE,
+    ARCHIVE_FILE,
+    CONFIG_FILE,
+    EVENT_LOG_FILE,
+    HISTORY_FILE,
+    COMMAND_HISTORY_FILE,
+];
+const APP_DIRECTORIES: [&str; 2] = [SNAPSHOT_DIRECTORY, BACKUP_DIRECTORY];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Ics,
+    TodoTxt,
+    Markdown,
+    Taskwarrior,
+    Html,
+}
+
+impl ExportFormat {
+    pub fn all() -> Vec<ExportFormat> {
+        vec![ExportFormat::Ics, ExportFormat::TodoTxt, ExportFormat::Markdown, ExportFormat::Taskwarrior, ExportFormat::Html]
+    }
+
+    pub fn default_file_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Ics => "tasks.ics",
+            ExportFormat::TodoTxt => "todo.txt",
+            ExportFormat::Markdown => "tasks.md",
+            ExportFormat::Taskwarrior => "tasks.json",
+            ExportFormat::Html => "tasks.html",
+        }
+    }
+}
+
+impl std::fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ExportFormat::Ics => write!(f, "ics"),
+            ExportFormat::TodoTxt => write!(f, "todo.txt"),
+            ExportFormat::Markdown => write!(f, "markdown"),
+            ExportFormat::Taskwarrior => write!(f, "taskwarrior"),
+            ExportFormat::Html => write!(f, "html"),
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ExportFormat, Self::Err> {
+        match input.to_lowercase().trim() {
+            "ics" | "ical" | "icalendar" => Ok(ExportFormat::Ics),
+            "todo.txt" | "todotxt" => Ok(ExportFormat::TodoTxt),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            "taskwarrior" | "tw" => Ok(ExportFormat::Taskwarrior),
+            "html" => Ok(ExportFormat::Html),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ExportOptions {
+    // How tasks are grouped in formats with sections
+    pub grouping: Grouping,
+    // From the configuration, written into ids so they are only matched by this list
+    pub list_id: u32,
+}
+
+pub fn export_tasks(tasks: &[&Task], format: ExportFormat, path: &str, options: &ExportOptions) -> Result<(), Box<dyn Error>> {
+    if is_app_file(path) {
+        return Err(format!("{} holds the app's own data", path).into());
+    }
+
+    let contents = match format {
+        ExportFormat::Ics => ical::to_ics(tasks, options.list_id),
+        ExportFormat::TodoTxt => todo_txt::to_todo_txt(tasks),
+        ExportFormat::Markdown => markdown::to_markdown(tasks, options.grouping, options.list_id),
+        ExportFormat::Taskwarrior => taskwarrior::to_taskwarrior_json(tasks, options.list_id)?,
+        ExportFormat::Html => html_report::to_html(tasks),
+    };
+
+    fs::write(path, contents)?;
+    Ok(())
+}
+
+// Whether the path is one of the app's files, however it is written
+pub fn is_app_file(path: &str) -> bool {
+    let app_directory = match std::env::current_dir().ok().and_then(|directory| resolve(&directory)) {
+        Some(app_directory) => app_directory,
+        None => return false,
+    };
+    let path = match resolve(Path::new(path)) {
+        Some(path) => path,
+        None => return false,
+    };
+
+    APP_FILES.iter().any(|file| path == app_directory.join(file))
+        || APP_DIRECTORIES.iter().any(|directory| path.starts_with(app_directory.join(directory)))
+}
+
+// The absolute path with links followed, also for files that don't exist yet
+fn resolve(path: &Path) -> Option<PathBuf> {
+    if let Ok(path) = path.canonicalize() {
+        return Some(path);
+    }
+
+    let file_name = path.file_name()?;
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(resolve(parent)?.join(file_name))
+}
@@ -0,0 +1,60 @@
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{File, OpenOptions, TryLockError};
+use std::hash::Hasher;
+
+// Advisory lock held on a sidecar file, so the data file itself can be
+// truncated and rewritten while the lock is held.
+pub struct FileLock {
+    file: File,
+}
+
+impl FileLock {
+    pub fn acquire(path: &str) -> std::io::Result<FileLock> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                println!("Waiting for another instance to release {}", path);
+                file.lock()?;
+            }
+            Err(TryLockError::Error(e)) => return Err(e),
+        }
+
+        Ok(FileLock { file })
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
+
+// Snapshot of a file's contents used to tell whether somebody else wrote to it.
+// We hash instead of comparing mtimes, as those are too coarse on some
+// filesystems and change even when identical contents are written back.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileFingerprint {
+    length: usize,
+    hash: u64,
+}
+
+impl FileFingerprint {
+    // Returns None when the file does not exist (yet)
+    pub fn of(path: &str) -> Option<FileFingerprint> {
+        let contents = std::fs::read(path).ok()?;
+
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&contents);
+
+        Some(FileFingerprint {
+            length: contents.len(),
+            hash: hasher.finish(),
+        })
+    }
+}
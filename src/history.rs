@@ -0,0 +1,225 @@
+use std::io::{Read, Write};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::task::Task;
+
+// Number of operations kept for undo, both in memory and on disk
+pub const HISTORY_LIMIT: usize = 100;
+pub const HISTORY_FILE: &str = "history.bin";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Change {
+    Added { index: usize, task: Task },
+    Updated { before: Task, after: Task },
+    Deleted { index: usize, task: Task },
+    // Moved between the task list and the archive
+    Archived { index: usize, task: Task },
+    Unarchived { index: usize, task: Task },
+}
+
+impl Change {
+    pub fn task_id(&self) -> usize {
+        match self {
+            Change::Added { task, .. } => task.id,
+            Change::Updated { after, .. } => after.id,
+            Change::Deleted { task, .. } => task.id,
+            Change::Archived { task, .. } => task.id,
+            Change::Unarchived { task, .. } => task.id,
+        }
+    }
+
+    pub fn inverse(&self) -> Change {
+        match self {
+            Change::Added { index, task } => Change::Deleted {
+                index: *index,
+                task: task.clone(),
+            },
+            Change::Updated { before, after } => Change::Updated {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            Change::Deleted { index, task } => Change::Added {
+                index: *index,
+                task: task.clone(),
+            },
+            Change::Archived { index, task } => Change::Unarchived {
+                index: *index,
+                task: task.clone(),
+            },
+            Change::Unarchived { index, task } => Change::Archived {
+                index: *index,
+                task: task.clone(),
+            },
+        }
+    }
+
+    // index is the task's position in the task list before it was removed from it
+    pub fn apply(&self, tasks: &mut Vec<Task>, archived_tasks: &mut Vec<Task>) {
+        match self {
+            Change::Added { index, task } => {
+                let index = (*index).min(tasks.len());
+                tasks.insert(index, task.clone());
+            }
+            Change::Updated { after, .. } => {
+                if let Some(task) = tasks.iter_mut().find(|t| t.id == after.id) {
+                    *task = after.clone();
+                }
+            }
+            Change::Deleted { task, .. } => {
+                tasks.retain(|t| t.id != task.id);
+            }
+            Change::Archived { task, .. } => {
+                tasks.retain(|t| t.id != task.id);
+                archived_tasks.push(task.clone());
+            }
+            Change::Unarchived { index, task } => {
+                archived_tasks.retain(|t| t.id != task.id);
+                let index = (*index).min(tasks.len());
+                tasks.insert(index, task.clone());
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Change {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Change::Added { task, .. } => write!(f, "add task {} ({})", task.id, task.title),
+            Change::Updated { before, after } => {
+                let action = match (before.is_deleted(), after.is_deleted()) {
+                    (false, true) => "trash",
+                    (true, false) => "restore",
+                    _ => "update",
+                };
+                write!(f, "{} task {} ({})", action, after.id, after.title)
+            }
+            Change::Deleted { task, .. } => write!(f, "delete task {} ({})", task.id, task.title),
+            Change::Archived { task, .. } => write!(f, "archive task {} ({})", task.id, task.title),
+            Change::Unarchived { task, .. } => {
+                write!(f, "unarchive task {} ({})", task.id, task.title)
+            }
+        }
+    }
+}
+
+// One undoable step, which may consist of several changes
+// (e.g. adding a task and then setting its description and due date)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Operation {
+    pub changes: Vec<Change>,
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let first_change = match self.changes.first() {
+            Some(change) => change,
+            None => return write!(f, "nothing"),
+        };
+
+        if self.changes.iter().all(|c| c.task_id() == first_change.task_id()) {
+            write!(f, "{}", first_change)
+        } else {
+            write!(f, "{} changes", self.changes.len())
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct History {
+    undo_stack: Vec<Operation>,
+    redo_stack: Vec<Operation>,
+    #[serde(skip)]
+    pending: Vec<Change>,
+    #[serde(skip)]
+    group_depth: usize,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History::default()
+    }
+
+    pub fn load_or_create_history(name: &str) -> Self {
+        let file = std::fs::File::open(name);
+        match file {
+            Ok(mut file) => {
+                let mut encoded_history = Vec::new();
+                file.read_to_end(&mut encoded_history).unwrap();
+                match bincode::deserialize(&encoded_history) {
+                    Ok(history) => history,
+                    Err(e) => {
+                        println!("Could not read history, starting with an empty one: {}", e);
+                        History::new()
+                    }
+                }
+            }
+            Err(_) => History::new(),
+        }
+    }
+
+    pub fn save_history(&self, name: &str) {
+        let mut file = std::fs::File::create(name).unwrap();
+        let encoded_history = bincode::serialize(&self).unwrap();
+        file.write_all(&encoded_history).unwrap();
+        file.flush().unwrap();
+    }
+
+    // Changes recorded until the matching end_operation are undone as one step
+    pub fn begin_operation(&mut self) {
+        self.group_depth += 1;
+    }
+
+    pub fn end_operation(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+
+        if self.group_depth == 0 && !self.pending.is_empty() {
+            let changes = std::mem::take(&mut self.pending);
+            self.push(Operation { changes });
+        }
+    }
+
+    pub fn record(&mut self, change: Change) {
+        if self.group_depth > 0 {
+            self.pending.push(change);
+        } else {
+            self.push(Operation {
+                changes: vec![change],
+            });
+        }
+    }
+
+    fn push(&mut self, operation: Operation) {
+        self.undo_stack.push(operation);
+        self.redo_stack.clear();
+
+        if self.undo_stack.len() > HISTORY_LIMIT {
+            let overflow = self.undo_stack.len() - HISTORY_LIMIT;
+            self.undo_stack.drain(..overflow);
+        }
+    }
+
+    // Reverts the last operation on tasks and returns it
+    pub fn undo(&mut self, tasks: &mut Vec<Task>, archived_tasks: &mut Vec<Task>) -> Option<Operation> {
+        let operation = self.undo_stack.pop()?;
+
+        for change in operation.changes.iter().rev() {
+            change.inverse().apply(tasks, archived_tasks);
+        }
+
+        self.redo_stack.push(operation.clone());
+        Some(operation)
+    }
+
+    // Reapplies the last undone operation on tasks and returns it
+    pub fn redo(&mut self, tasks: &mut Vec<Task>, archived_tasks: &mut Vec<Task>) -> Option<Operation> {
+        let operation = self.redo_stack.pop()?;
+
+        for change in &operation.changes {
+            change.apply(tasks, archived_tasks);
+        }
+
+        self.undo_stack.push(operation.clone());
+        Some(operation)
+    }
+}
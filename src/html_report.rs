@@ -0,0 +1,156 @@
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::priority::Priority;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+const STYLE: &str = r#"
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { margin-bottom: 0.2em; }
+.generated { color: #777; margin-top: 0; }
+input { padding: 0.4em; width: 20em; margin-bottom: 1em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2em; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.4em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f4f4f4; cursor: pointer; user-select: none; }
+th.ascending::after { content: " \25B2"; }
+th.descending::after { content: " \25BC"; }
+td.description { white-space: pre-line; color: #555; }
+tr.overdue td { background: #fdecea; }
+tr.overdue td.due { color: #c62828; font-weight: bold; }
+.tag { background: #e0f2f1; border-radius: 3px; padding: 0 0.3em; margin-right: 0.2em; }
+"#;
+
+// Sorts a table by the column header clicked, again to reverse, and hides rows
+// not containing the text typed into the filter
+const SCRIPT: &str = r#"
+document.querySelectorAll("th").forEach(function (header) {
+  header.addEventListener("click", function () {
+    var table = header.closest("table");
+    var index = Array.prototype.indexOf.call(header.parentNode.children, header);
+    var ascending = !header.classList.contains("ascending");
+    table.querySelectorAll("th").forEach(function (h) { h.classList.remove("ascending", "descending"); });
+    header.classList.add(ascending ? "ascending" : "descending");
+    var body = table.tBodies[0];
+    var rows = Array.prototype.slice.call(body.rows);
+    rows.sort(function (a, b) {
+      var x = a.cells[index].getAttribute("data-sort") || a.cells[index].textContent;
+      var y = b.cells[index].getAttribute("data-sort") || b.cells[index].textContent;
+      var result = isNaN(x) || isNaN(y) || x === "" || y === "" ? x.localeCompare(y) : x - y;
+      return ascending ? result : -result;
+    });
+    rows.forEach(function (row) { body.appendChild(row); });
+  });
+});
+document.getElementById("filter").addEventListener("input", function (event) {
+  var text = event.target.value.toLowerCase();
+  document.querySelectorAll("tbody tr").forEach(function (row) {
+    row.style.display = row.textContent.toLowerCase().indexOf(text) === -1 ? "none" : "";
+  });
+});
+"#;
+
+// A page with a table of tasks per status that works without any other files
+pub fn to_html(tasks: &[&Task]) -> String {
+    let generated_at = NaiveDateTimeWrapper::now().0.format("%d.%m.%Y %H:%M");
+    let overdue = tasks.iter().filter(|t| t.is_overdue()).count();
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Tasks</title>\n");
+    html.push_str(&format!("<style>{}</style>\n", STYLE));
+    html.push_str("</head>\n<body>\n<h1>Tasks</h1>\n");
+    html.push_str(&format!(
+        "<p class=\"generated\">Generated {} &middot; {} tasks, {} overdue</p>\n",
+        generated_at,
+        tasks.len(),
+        overdue
+    ));
+    html.push_str("<input id=\"filter\" type=\"search\" placeholder=\"Filter tasks\">\n");
+
+    for status in TaskStatus::all() {
+        let group: Vec<&&Task> = tasks.iter().filter(|t| t.status == status).collect();
+        html.push_str(&format!("<h2>{} ({})</h2>\n", status, group.len()));
+        if group.is_empty() {
+            continue;
+        }
+
+        html.push_str("<table>\n<thead><tr><th>Id</th><th>Title</th><th>Description</th><th>Due</th><th>Priority</th><th>Tags</th></tr></thead>\n<tbody>\n");
+        for task in group {
+            html.push_str(&task_row(task));
+        }
+        html.push_str("</tbody>\n</table>\n");
+    }
+
+    html.push_str(&format!("<script>{}</script>\n</body>\n</html>\n", SCRIPT));
+    html
+}
+
+fn task_row(task: &Task) -> String {
+    let row_class = if task.is_overdue() { " class=\"overdue\"" } else { "" };
+    let (due_sort, due) = match task.due_date {
+        Some(due_date) => (
+            due_date.0.format("%Y-%m-%d %H:%M").to_string(),
+            due_date.0.format("%d.%m.%Y %H:%M").to_string(),
+        ),
+        None => (String::new(), String::new()),
+    };
+    let priority_sort = match task.priority {
+        Some(Priority::High) => 3,
+        Some(Priority::Medium) => 2,
+        Some(Priority::Low) => 1,
+        None => 0,
+    };
+    let tags: String = task
+        .tags
+        .iter()
+        .map(|tag| format!("<span class=\"tag\">+{}</span>", escape(tag)))
+        .collect();
+
+    format!(
+        "<tr{}><td>{}</td><td>{}</td><td class=\"description\">{}</td><td class=\"due\" data-sort=\"{}\">{}</td><td data-sort=\"{}\">{}</td><td>{}</td></tr>\n",
+        row_class,
+        task.id,
+        escape(&task.title),
+        escape(task.description.as_deref().unwrap_or_default()),
+        due_sort,
+        due,
+        priority_sort,
+        task.priority.map(|p| p.to_string()).unwrap_or_default(),
+        tags
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_task_text_and_marks_overdue_tasks() {
+        let mut overdue = Task::new(1, "Fix <script>alert('x')</script> & more".to_string());
+        overdue.set_due_date(NaiveDateTimeWrapper::parse_from_str("2020-01-02 09:00:00", "%Y-%m-%d %H:%M:%S").unwrap());
+        overdue.set_priority(Some(Priority::High));
+        overdue.set_tags(vec!["a\"b".to_string()]);
+        let mut done = Task::new(2, "Done".to_string());
+        done.set_status(TaskStatus::Completed);
+        done.set_due_date(NaiveDateTimeWrapper::parse_from_str("2020-01-02 09:00:00", "%Y-%m-%d %H:%M:%S").unwrap());
+
+        let html = to_html(&[&overdue, &done]);
+
+        assert!(html.contains("<td>Fix &lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; &amp; more</td>"));
+        assert!(!html.contains("<script>alert"));
+        assert!(html.contains("<span class=\"tag\">+a&quot;b</span>"));
+        assert!(html.contains("<tr class=\"overdue\"><td>1</td>"));
+        // Completed tasks aren't overdue
+        assert!(html.contains("<tr><td>2</td>"));
+        assert!(html.contains("data-sort=\"2020-01-02 09:00\">02.01.2020 09:00</td><td data-sort=\"3\">High</td>"));
+        assert!(html.contains("2 tasks, 1 overdue"));
+        assert!(html.contains("<h2>Not Started (1)</h2>") && html.contains("<h2>In Progress (0)</h2>"));
+    }
+}
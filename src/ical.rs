@@ -0,0 +1,438 @@
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Utc};
+
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::priority::Priority;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+const PRODUCT_ID: &str = "-//realsnack//rust-console-to-do//EN";
+const UID_DOMAIN: &str = "rust-console-to-do";
+// Lines longer than this many bytes are folded, see RFC 5545 section 3.1
+const MAX_LINE_LENGTH: usize = 75;
+const LOCAL_TIME_FORMAT: &str = "%Y%m%dT%H%M%S";
+const UTC_TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+const DATE_FORMAT: &str = "%Y%m%d";
+// Items due on a day without a time are due at the end of it
+const END_OF_DAY: (u32, u32) = (23, 59);
+
+// Writes tasks as an RFC 5545 calendar with one VTODO per task
+pub fn to_ics(tasks: &[&Task], list_id: u32) -> String {
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:{}", PRODUCT_ID),
+    ];
+    let timestamp = Utc::now().format(UTC_TIME_FORMAT).to_string();
+
+    for task in tasks {
+        lines.push("BEGIN:VTODO".to_string());
+        let uid = task.uid.clone().unwrap_or_else(|| task_uid(task.id, list_id));
+        lines.push(format!("UID:{}", escape_text(&uid)));
+        lines.push(format!("DTSTAMP:{}", timestamp));
+        if let Some(created_at) = task.created_at {
+            lines.push(format!("CREATED:{}", utc_time(created_at)));
+        }
+        lines.push(format!("SUMMARY:{}", escape_text(&task.title)));
+        if let Some(description) = &task.description {
+            lines.push(format!("DESCRIPTION:{}", escape_text(description)));
+        }
+        // Due dates have no time zone, so they are written as floating local times
+        if let Some(due_date) = task.due_date {
+            lines.push(format!("DUE:{}", due_date.0.format(LOCAL_TIME_FORMAT)));
+        }
+        lines.push(format!("STATUS:{}", status_name(&task.status)));
+        if let Some(completed_at) = task.completed_at {
+            lines.push(format!("COMPLETED:{}", utc_time(completed_at)));
+        }
+        if let Some(priority) = task.priority {
+            lines.push(format!("PRIORITY:{}", priority_value(priority)));
+        }
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|tag| escape_text(tag)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        lines.push("END:VTODO".to_string());
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.iter().map(|line| fold_line(line) + "\r\n").collect()
+}
+
+// Stays the same across exports so calendar clients update tasks instead of
+// duplicating them. The list id keeps tasks of different lists apart.
+pub fn task_uid(task_id: usize, list_id: u32) -> String {
+    format!("task-{}.{:08x}@{}", task_id, list_id, UID_DOMAIN)
+}
+
+// Id of the task a UID from task_uid belongs to, None for UIDs of other lists or applications
+pub fn task_id_from_uid(uid: &str, list_id: u32) -> Option<usize> {
+    let (task_id, uid_list_id) = uid
+        .strip_prefix("task-")?
+        .strip_suffix(&format!("@{}", UID_DOMAIN))?
+        .split_once('.')?;
+
+    if uid_list_id != format!("{:08x}", list_id) {
+        return None;
+    }
+    task_id.parse().ok()
+}
+
+// Reads the VTODOs of a calendar as tasks, and VEVENTs as tasks due at their
+// start when include_events is set. The tasks have id 0 and the UID of their item.
+pub fn from_ics(contents: &str, include_events: bool) -> Result<Vec<Task>, String> {
+    let mut tasks = Vec::new();
+    // The kind of component we are in and the properties read so far
+    let mut item: Option<(String, Vec<Property>)> = None;
+    // Alarms and other components nested in an item, their properties aren't the item's
+    let mut nested_components = 0;
+
+    for (line_number, line) in unfold_lines(contents) {
+        let property = match parse_property(&line) {
+            Some(property) => property,
+            None if line.trim().is_empty() => continue,
+            None => return Err(format!("Invalid line {}: {}", line_number, line)),
+        };
+        let value = property.value.to_uppercase();
+
+        match (property.name.as_str(), &mut item) {
+            ("BEGIN", None) if value == "VTODO" || (include_events && value == "VEVENT") => {
+                item = Some((value, Vec::new()))
+            }
+            ("BEGIN", Some(_)) => nested_components += 1,
+            ("END", Some(_)) if nested_components > 0 => nested_components -= 1,
+            ("END", Some((kind, properties))) if *kind == value => {
+                if let Some(task) = item_to_task(kind, properties)? {
+                    tasks.push(task);
+                }
+                item = None;
+            }
+            ("END", Some(_)) => {}
+            (_, Some((_, properties))) if nested_components == 0 => properties.push(property),
+            _ => {}
+        }
+    }
+
+    Ok(tasks)
+}
+
+fn status_name(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::NotStarted => "NEEDS-ACTION",
+        TaskStatus::InProgress => "IN-PROCESS",
+        TaskStatus::Completed => "COMPLETED",
+    }
+}
+
+// 1 is the highest priority and 9 the lowest, 0 means undefined
+fn priority_value(priority: Priority) -> u8 {
+    match priority {
+        Priority::High => 1,
+        Priority::Medium => 5,
+        Priority::Low => 9,
+    }
+}
+
+// CREATED and COMPLETED have to be in UTC, our times are local
+fn utc_time(date_time: NaiveDateTimeWrapper) -> String {
+    date_time.to_utc().format(UTC_TIME_FORMAT).to_string()
+}
+
+struct Property {
+    name: String,
+    // Parameters like TZID or VALUE, names in upper case
+    parameters: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|(parameter, _)| parameter == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+fn item_to_task(kind: &str, properties: &[Property]) -> Result<Option<Task>, String> {
+    let property = |name: &str| properties.iter().find(|p| p.name == name);
+    let text = |name: &str| property(name).map(|p| unescape_text(&p.value));
+
+    let uid = text("UID");
+    let title = text("SUMMARY").filter(|summary| !summary.trim().is_empty());
+    let title = match title {
+        Some(title) => title,
+        None => return Err(format!("{} {} has no summary", kind, uid.unwrap_or_default())),
+    };
+    let status = text("STATUS").unwrap_or_default().to_uppercase();
+
+    let mut task = Task::new(0, title);
+    task.set_uid(uid);
+    task.description = text("DESCRIPTION").filter(|description| !description.is_empty());
+
+    if kind == "VEVENT" {
+        if status == "CANCELLED" {
+            return Ok(None);
+        }
+        task.due_date = property("DTSTART").map(parse_date_time).transpose()?;
+        return Ok(Some(task));
+    }
+
+    task.due_date = property("DUE").map(parse_date_time).transpose()?;
+    let (status, completed_at) = match status.as_str() {
+        "IN-PROCESS" => (TaskStatus::InProgress, None),
+        "COMPLETED" | "CANCELLED" => {
+            let completed_at = property("COMPLETED").map(parse_date_time).transpose()?;
+            (TaskStatus::Completed, Some(completed_at.unwrap_or_else(NaiveDateTimeWrapper::now)))
+        }
+        _ => (TaskStatus::NotStarted, None),
+    };
+    task.set_status(status);
+    task.set_completed_at(completed_at);
+    task.set_priority(text("PRIORITY").and_then(|p| p.trim().parse().ok()).and_then(priority_from_value));
+    task.set_created_at(property("CREATED").map(parse_date_time).transpose()?);
+
+    // Tags can't contain spaces, so "Project X" becomes Project-X
+    let tags = properties
+        .iter()
+        .filter(|p| p.name == "CATEGORIES")
+        .flat_map(|p| split_list(&p.value))
+        .map(|tag| tag.split_whitespace().collect::<Vec<&str>>().join("-"))
+        .filter(|tag| !tag.is_empty())
+        .collect();
+    task.set_tags(tags);
+
+    Ok(Some(task))
+}
+
+fn priority_from_value(value: u8) -> Option<Priority> {
+    match value {
+        1..=4 => Some(Priority::High),
+        5 => Some(Priority::Medium),
+        6..=9 => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+// Times in UTC are converted to local time. Times with a TZID are taken as
+// local as well, as we have no time zone database.
+fn parse_date_time(property: &Property) -> Result<NaiveDateTimeWrapper, String> {
+    let value = property.value.trim();
+    let invalid = || format!("Invalid date in {}: {}", property.name, value);
+
+    let is_date = property.parameter("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE"));
+    if is_date || !value.contains('T') {
+        let date = NaiveDate::parse_from_str(value, DATE_FORMAT).map_err(|_| invalid())?;
+        let (hour, minute) = END_OF_DAY;
+        let time = NaiveTime::from_hms_opt(hour, minute, 0).ok_or_else(invalid)?;
+        return Ok(NaiveDateTimeWrapper::new(date.and_time(time)));
+    }
+
+    if let Some(utc) = value.strip_suffix('Z') {
+        let date_time = NaiveDateTime::parse_from_str(utc, LOCAL_TIME_FORMAT).map_err(|_| invalid())?;
+        return Ok(NaiveDateTimeWrapper::from_utc(date_time));
+    }
+
+    NaiveDateTime::parse_from_str(value, LOCAL_TIME_FORMAT)
+        .map(NaiveDateTimeWrapper::new)
+        .map_err(|_| invalid())
+}
+
+// Joins folded lines back together, along with the line number they start on
+fn unfold_lines(contents: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some((_, last))) => last.push_str(continuation),
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+
+    lines
+}
+
+// Splits NAME;PARAM=value:VALUE, colons and semicolons in quoted parameter values
+// don't count
+fn parse_property(line: &str) -> Option<Property> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find(|(_, c)| {
+        if *c == '"' {
+            in_quotes = !in_quotes;
+        }
+        *c == ':' && !in_quotes
+    })?;
+    let (head, value) = (&line[..colon.0], &line[colon.0 + 1..]);
+
+    let mut parts = head.split(';');
+    let name = parts.next()?.trim().to_uppercase();
+    if name.is_empty() {
+        return None;
+    }
+    let parameters = parts
+        .filter_map(|parameter| parameter.split_once('='))
+        .map(|(name, value)| (name.trim().to_uppercase(), value.trim_matches('"').to_string()))
+        .collect();
+
+    Some(Property {
+        name,
+        parameters,
+        value: value.to_string(),
+    })
+}
+
+// Splits a list of escaped values like CATEGORIES at the commas that aren't escaped
+fn split_list(value: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut current = String::new();
+    let mut escaped = false;
+
+    for c in value.chars() {
+        match c {
+            _ if escaped => {
+                current.push('\\');
+                current.push(c);
+                escaped = false;
+            }
+            '\\' => escaped = true,
+            ',' => values.push(unescape_text(&std::mem::take(&mut current))),
+            _ => current.push(c),
+        }
+    }
+    values.push(unescape_text(&current));
+
+    values
+}
+
+fn unescape_text(text: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => unescaped.push('\n'),
+            Some(escaped) => unescaped.push(escaped),
+            None => unescaped.push('\\'),
+        }
+    }
+
+    unescaped
+}
+
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+// Splits long lines into lines of at most MAX_LINE_LENGTH bytes, continuation
+// lines start with a space. Multi-byte characters are never split.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+
+    for c in line.chars() {
+        if length + c.len_utf8() > MAX_LINE_LENGTH {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+
+    folded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date_time(date_time: &str) -> NaiveDateTimeWrapper {
+        NaiveDateTimeWrapper::parse_from_str(date_time, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn imports_exported_tasks() {
+        let mut task = Task::new(4, "Write report, part 1; draft".to_string());
+        task.set_description("First line\nSecond line with a \\ backslash".to_string());
+        task.set_due_date(date_time("2024-03-01 17:30:00"));
+        task.set_status(TaskStatus::Completed);
+        task.set_completed_at(Some(date_time("2024-02-28 09:15:00")));
+        task.set_priority(Some(Priority::High));
+        task.set_tags(vec!["work".to_string(), "reports".to_string()]);
+        let mut other = Task::new(7, "x".repeat(200));
+        other.set_status(TaskStatus::InProgress);
+
+        let tasks = from_ics(&to_ics(&[&task, &other], 0xabc), false).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].title, task.title);
+        assert_eq!(tasks[0].description, task.description);
+        assert_eq!(tasks[0].due_date, task.due_date);
+        assert_eq!(tasks[0].status, TaskStatus::Completed);
+        assert_eq!(tasks[0].completed_at, task.completed_at);
+        assert_eq!(tasks[0].priority, Some(Priority::High));
+        assert_eq!(tasks[0].tags, task.tags);
+        assert_eq!(tasks[0].uid.as_deref().and_then(|uid| task_id_from_uid(uid, 0xabc)), Some(4));
+        assert_eq!(tasks[1].title, other.title);
+        assert_eq!(tasks[1].status, TaskStatus::InProgress);
+        assert_eq!(tasks[1].uid.as_deref().and_then(|uid| task_id_from_uid(uid, 1)), None);
+    }
+
+    #[test]
+    fn ignores_properties_of_nested_alarms() {
+        let contents = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VTODO\r\n\
+            UID:1@example.com\r\n\
+            SUMMARY:Pay rent\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-PT15M\r\n\
+            ACTION:DISPLAY\r\n\
+            SUMMARY:Alarm\r\n\
+            DESCRIPTION:Reminder\r\n\
+            END:VALARM\r\n\
+            DUE;VALUE=DATE:20240301\r\n\
+            END:VTODO\r\n\
+            END:VCALENDAR\r\n";
+
+        let tasks = from_ics(contents, false).unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].title, "Pay rent");
+        assert_eq!(tasks[0].description, None);
+        assert_eq!(tasks[0].due_date, Some(date_time("2024-03-01 23:59:00")));
+    }
+
+    #[test]
+    fn imports_events_only_when_asked_to() {
+        let contents = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Meeting\r\n\
+            DTSTART:20240301T100000\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Cancelled meeting\r\n\
+            STATUS:CANCELLED\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        assert!(from_ics(contents, false).unwrap().is_empty());
+        let tasks = from_ics(contents, true).unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].title, "Meeting");
+        assert_eq!(tasks[0].due_date, Some(date_time("2024-03-01 10:00:00")));
+    }
+
+    #[test]
+    fn refuses_items_without_summary() {
+        let contents = "BEGIN:VCALENDAR\r\nBEGIN:VTODO\r\nUID:2\r\nEND:VTODO\r\nEND:VCALENDAR\r\n";
+
+        assert!(from_ics(contents, false).is_err());
+    }
+}
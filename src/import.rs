@@ -0,0 +1,94 @@
+use std::error::Error;
+use std::fs;
+use std::str::FromStr;
+
+use crate::ical;
+use crate::markdown;
+use crate::task::Task;
+use crate::taskwarrior;
+use crate::todo_txt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImportFormat {
+    Ics,
+    TodoTxt,
+    Markdown,
+    Taskwarrior,
+}
+
+impl ImportFormat {
+    pub fn all() -> Vec<ImportFormat> {
+        vec![ImportFormat::Ics, ImportFormat::TodoTxt, ImportFormat::Markdown, ImportFormat::Taskwarrior]
+    }
+
+    // todo.txt has no place for descriptions, so a missing one wasn't removed
+    pub fn has_descriptions(&self) -> bool {
+        *self != ImportFormat::TodoTxt
+    }
+}
+
+impl std::fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ImportFormat::Ics => write!(f, "ics"),
+            ImportFormat::TodoTxt => write!(f, "todo.txt"),
+            ImportFormat::Markdown => write!(f, "markdown"),
+            ImportFormat::Taskwarrior => write!(f, "taskwarrior"),
+        }
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ImportFormat, Self::Err> {
+        match input.to_lowercase().trim() {
+            "ics" | "ical" | "icalendar" => Ok(ImportFormat::Ics),
+            "todo.txt" | "todotxt" => Ok(ImportFormat::TodoTxt),
+            "markdown" | "md" => Ok(ImportFormat::Markdown),
+            "taskwarrior" | "tw" => Ok(ImportFormat::Taskwarrior),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ImportOptions {
+    // Turns calendar events into tasks due at their start
+    pub include_events: bool,
+    // Only ids written by this list are matched to its tasks
+    pub list_id: u32,
+}
+
+// Tasks read from the file at path, they have id 0 until they are added to the list
+pub fn read_tasks(format: ImportFormat, path: &str, options: &ImportOptions) -> Result<Vec<Task>, Box<dyn Error>> {
+    let contents = fs::read_to_string(path)?;
+
+    let tasks = match format {
+        ImportFormat::Ics => ical::from_ics(&contents, options.include_events)?,
+        ImportFormat::TodoTxt => todo_txt::from_todo_txt(&contents)?,
+        ImportFormat::Markdown => markdown::from_markdown(&contents, options.list_id)?,
+        ImportFormat::Taskwarrior => taskwarrior::from_taskwarrior_json(&contents, options.list_id)?,
+    };
+
+    Ok(tasks)
+}
+
+// Markdown files are synced both ways: after an import, items without the id
+// comment of this list get one with the id of the task they were imported to,
+// given in the order they were read. Nothing else in the file changes. Returns
+// whether the file was written.
+pub fn write_back(format: ImportFormat, path: &str, task_ids: &[usize], list_id: u32) -> Result<bool, Box<dyn Error>> {
+    if format != ImportFormat::Markdown {
+        return Ok(false);
+    }
+
+    let contents = fs::read_to_string(path)?;
+    let updated_contents = markdown::set_id_comments(&contents, task_ids, list_id);
+    if updated_contents == contents {
+        return Ok(false);
+    }
+
+    fs::write(path, updated_contents)?;
+    Ok(true)
+}
@@ -0,0 +1,117 @@
+use std::cell::RefCell;
+
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Config, Context, Editor, Helper};
+
+pub const COMMAND_HISTORY_FILE: &str = "command_history.txt";
+const PROMPT: &str = "> ";
+
+// Completes the whole line from the values that are valid for the current prompt
+struct InputHelper {
+    completions: Vec<String>,
+}
+
+impl Completer for InputHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let typed = line[..pos].to_lowercase();
+        let matches = self
+            .completions
+            .iter()
+            .filter(|completion| completion.to_lowercase().starts_with(&typed))
+            .cloned()
+            .collect();
+
+        Ok((0, matches))
+    }
+}
+
+impl Hinter for InputHelper {
+    type Hint = String;
+}
+
+impl Highlighter for InputHelper {}
+
+impl Validator for InputHelper {}
+
+impl Helper for InputHelper {}
+
+thread_local! {
+    // Created on first use so the history file is only read once
+    static EDITOR: RefCell<Option<Editor<InputHelper, DefaultHistory>>> = const { RefCell::new(None) };
+}
+
+fn create_editor() -> Option<Editor<InputHelper, DefaultHistory>> {
+    let config = Config::builder()
+        .auto_add_history(false)
+        .history_ignore_dups(true)
+        .ok()?
+        .build();
+
+    let mut editor = Editor::with_config(config).ok()?;
+    // The history file doesn't exist before the first command was entered
+    let _ = editor.load_history(COMMAND_HISTORY_FILE);
+
+    Some(editor)
+}
+
+// Reads a line with arrow key editing, Ctrl-R history search and tab completion
+// of the given values. Commands are remembered in the history file. None when
+// the input ended (Ctrl-D or a closed stdin) or Ctrl-C was pressed.
+pub fn read_line(completions: Vec<String>, remember: bool) -> Option<String> {
+    EDITOR.with(|editor| {
+        let mut editor = editor.borrow_mut();
+        if editor.is_none() {
+            *editor = create_editor();
+        }
+
+        let editor = match editor.as_mut() {
+            Some(editor) => editor,
+            None => return read_plain_line(),
+        };
+
+        editor.set_helper(Some(InputHelper { completions }));
+        let input = match editor.readline(PROMPT) {
+            Ok(input) => input.trim().to_string(),
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => return None,
+            Err(e) => {
+                println!("Failed to read line: {}", e);
+                return None;
+            }
+        };
+
+        if remember && !input.is_empty() {
+            let _ = editor.add_history_entry(input.as_str());
+            if let Err(e) = editor.save_history(COMMAND_HISTORY_FILE) {
+                println!("Failed to save command history: {}", e);
+            }
+        }
+
+        Some(input)
+    })
+}
+
+// Used when the terminal can't be set up for line editing
+fn read_plain_line() -> Option<String> {
+    use std::io::Write;
+
+    let mut input = String::new();
+    print!("{}", PROMPT);
+    std::io::stdout().flush().expect("Failed to flush");
+    let read = std::io::stdin()
+        .read_line(&mut input)
+        .expect("Failed to read line");
+
+    (read > 0).then(|| input.trim().to_string())
+}
@@ -1,26 +1,66 @@
+pub mod backup;
+pub mod board;
+pub mod calendar;
 pub mod command;
+pub mod command_line;
 pub mod configuration;
+pub mod event_log;
+pub mod export;
+pub mod file_lock;
+pub mod history;
+pub mod html_report;
+pub mod ical;
+pub mod import;
+pub mod line_editor;
+pub mod markdown;
+pub mod merge;
+pub mod migration;
 pub mod naive_date_time_wrapper;
 pub mod persistence;
+pub mod priority;
+pub mod query;
+pub mod search;
+pub mod stats;
+pub mod storage;
 pub mod supported_persistence;
+pub mod table;
 pub mod task;
 pub mod task_list;
 pub mod task_status;
+pub mod taskwarrior;
+pub mod todo_txt;
+pub mod tui;
 
 use colorize::AnsiColor;
 
 use std::{
+    collections::{BTreeMap, HashMap},
     io::{self, Write},
     str::FromStr,
 };
 
+use chrono::Datelike;
 use command::SupportedCommand;
-use configuration::Configuration;
+use command_line::{TaskArguments, TaskSelector};
+use event_log::Event;
+use export::{ExportFormat, ExportOptions};
+use configuration::{Configuration, CONFIG_FILE};
+use history::{Change, History, HISTORY_FILE};
+use import::{ImportFormat, ImportOptions};
+use merge::Resolution;
+use priority::Priority;
+use query::Filter;
+use supported_persistence::SupportedPersistence;
+use persistence::SaveError;
+use table::{Column, ListLayout};
+use task::Task;
 use task_list::TaskList;
 
 use crate::{naive_date_time_wrapper::NaiveDateTimeWrapper, task_status::TaskStatus};
 
 const CLEAR_SCREEN: &str = "\x1B[2J";
+// Every day is checked for tasks due, so the agenda is kept to ten years
+const MAX_AGENDA_DAYS: u32 = 3660;
 
 fn main() {
     if cfg!(debug_assertions) {
@@ -29,13 +69,50 @@ fn main() {
         println!("{}", CLEAR_SCREEN);
     }
 
-    let mut config = Configuration::load_or_create_configuration("config.bin");
+    // `rust-console-to-do migrate --dry-run` shows what would be upgraded
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| arg == "migrate") {
+        migrate_files(&args[1..]);
+        return;
+    }
 
-    let mut list_of_tasks = TaskList::new();
-    let load_result = list_of_tasks.load_tasks_from_csv();
-    if let Ok(tasks) = load_result {
-        list_of_tasks.tasks = tasks;
-        list_of_tasks.update_task_counter(config.task_counter);
+    // Files written by older versions are upgraded before anything reads them
+    if let Err(e) = run_migrations(false) {
+        println!("Could not upgrade stored data: {}", e);
+        return;
+    }
+
+    let mut config = match Configuration::load_or_create_configuration(CONFIG_FILE) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let list_columns = parse_columns_argument();
+
+    let storage = match storage::storage_for(config.persistence, config.task_counter) {
+        Ok(storage) => storage,
+        Err(e) => {
+            println!("Could not open {} storage: {}", config.persistence, e);
+            return;
+        }
+    };
+    let mut list_of_tasks = TaskList::new(storage);
+    match list_of_tasks.load_tasks() {
+        Ok(problems) => {
+            for problem in problems {
+                println!("{}", problem.to_string().yellow());
+            }
+            // Other instances sharing a database count up its task counter
+            list_of_tasks.update_task_counter(config.task_counter.max(list_of_tasks.get_task_counter()));
+            list_of_tasks.history = History::load_or_create_history(HISTORY_FILE);
+        }
+        // Saving would overwrite tasks we couldn't read
+        Err(e) => {
+            println!("Could not load tasks: {}", e);
+            return;
+        }
     }
 
     println!(
@@ -43,26 +120,76 @@ fn main() {
         list_of_tasks.tasks.len()
     );
 
+    if let Some(trash_retention_days) = config.trash_retention_days {
+        let purged = list_of_tasks.purge_trash_older_than(trash_retention_days);
+        if purged > 0 {
+            println!("Purged {} tasks older than {} days from trash", purged, trash_retention_days);
+        }
+    }
+
+    if let Some(auto_archive_days) = config.auto_archive_days {
+        let archived = list_of_tasks.archive_tasks_completed_before(auto_archive_days);
+        if archived > 0 {
+            println!("Archived {} tasks completed more than {} days ago", archived, auto_archive_days);
+        }
+    }
+
     if config.task_counter == 0 {
         config.task_counter = list_of_tasks.get_highest_task_id();
         list_of_tasks.update_task_counter(config.task_counter);
     }
 
     loop {
-        let command = command_selection();
+        let (command, arguments) = command_selection();
         println!();
 
+        if !arguments.is_empty() && !command.takes_arguments() {
+            println!("{} doesn't take arguments, ignoring them", command);
+            println!();
+        }
+
         match command {
-            SupportedCommand::Add => add_task(&mut list_of_tasks),
-            SupportedCommand::List => list_tasks(&list_of_tasks),
-            SupportedCommand::Update => update_task(&mut list_of_tasks),
-            SupportedCommand::Delete => delete_task(&mut list_of_tasks),
+            SupportedCommand::Add => add_task(&mut list_of_tasks, &arguments),
+            SupportedCommand::List => {
+                list_tasks(&list_of_tasks, &config, list_columns.as_deref(), &arguments)
+            }
+            SupportedCommand::Search => search_tasks(&list_of_tasks, &arguments),
+            SupportedCommand::Update => update_task(&mut list_of_tasks, &arguments, &config.views),
+            SupportedCommand::Delete => delete_task(&mut list_of_tasks, &arguments, &config.views),
+            SupportedCommand::Trash => list_trash(&list_of_tasks),
+            SupportedCommand::Restore => restore_task(&mut list_of_tasks, &arguments),
+            SupportedCommand::EmptyTrash => empty_trash(&mut list_of_tasks),
+            SupportedCommand::Archive => archive_tasks(&mut list_of_tasks, &arguments),
+            SupportedCommand::Archived => search_archive(&list_of_tasks),
+            SupportedCommand::Unarchive => unarchive_task(&mut list_of_tasks, &arguments),
+            SupportedCommand::Undo => undo(&mut list_of_tasks),
+            SupportedCommand::Redo => redo(&mut list_of_tasks),
+            SupportedCommand::History => task_history(&list_of_tasks, &arguments),
+            SupportedCommand::AsOf => list_tasks_as_of(&list_of_tasks),
+            SupportedCommand::Tui => run_tui(&mut list_of_tasks),
+            SupportedCommand::Board => run_board(&mut list_of_tasks),
+            SupportedCommand::Calendar => show_calendar(&list_of_tasks),
+            SupportedCommand::Agenda => show_agenda(&list_of_tasks),
+            SupportedCommand::View => manage_views(&mut config, &arguments),
+            SupportedCommand::Stats => show_stats(&list_of_tasks, &arguments, &config.views),
+            SupportedCommand::Export => export_tasks(&list_of_tasks, config.list_id, &arguments),
+            SupportedCommand::Import => import_tasks(&mut list_of_tasks, config.list_id, &arguments),
+            SupportedCommand::Migrate => {
+                println!("{}", CLEAR_SCREEN);
+                migrate_files(&arguments);
+                press_enter();
+            }
+            SupportedCommand::Backend => switch_backend(&mut list_of_tasks, &mut config, &arguments),
+            SupportedCommand::Backup => manage_backups(&mut list_of_tasks, &arguments),
+            SupportedCommand::Config => update_configuration(&mut config),
             SupportedCommand::Help => help(),
             SupportedCommand::Clear => clear_screen(),
             SupportedCommand::Exit => {
-                list_of_tasks.save_tasks_to_csv();
+                if save_tasks(&mut list_of_tasks, config.backup_retention) {
+                    list_of_tasks.history.save_history(HISTORY_FILE);
+                }
                 config.task_counter = list_of_tasks.get_task_counter();
-                config.save_configuration("config.bin");
+                config.save_configuration(CONFIG_FILE);
                 println!("Exiting");
                 break;
             }
@@ -70,57 +197,128 @@ fn main() {
     }
 }
 
-fn command_selection() -> SupportedCommand {
+// Returns the command together with any arguments given after it on the same line
+fn command_selection() -> (SupportedCommand, Vec<String>) {
     println!();
     println!("Enter a command:");
     println!("  {} - adds a task", "add".bold().green());
     println!("  {} - lists all tasks", "list".bold().cyan());
+    println!("  {} - searches titles and descriptions", "search".bold().cyan());
     println!("  {} - updates a task", "update".bold().yellow());
-    println!("  {} - deletes a task", "delete".bold().red());
+    println!("  {} - moves a task to trash", "delete".bold().red());
+    println!("  {} - lists tasks in trash", "trash".bold().red());
+    println!("  {} - restores a task from trash", "restore".bold().green());
+    println!("  {} - permanently deletes tasks in trash", "empty-trash".bold().red());
+    println!("  {} - archives completed tasks", "archive".bold().cyan());
+    println!("  {} - searches archived tasks", "archived".bold().cyan());
+    println!("  {} - moves a task back from the archive", "unarchive".bold().cyan());
+    println!("  {} - undoes the last change", "undo".bold().blue());
+    println!("  {} - redoes the last undone change", "redo".bold().blue());
+    println!("  {} - shows all changes made to a task", "history".bold().blue());
+    println!("  {} - lists tasks as they were at a past date", "as-of".bold().blue());
+    println!("  {} - opens the full-screen interface", "tui".bold().magenta());
+    println!("  {} - shows tasks on a board by status", "board".bold().magenta());
+    println!("  {} - shows a month calendar of due dates", "calendar".bold().magenta());
+    println!("  {} - lists tasks due in the next days", "agenda".bold().magenta());
+    println!("  {} - lists, saves and deletes views for list", "view".bold().cyan());
+    println!("  {} - shows statistics and a burndown chart", "stats".bold().blue());
+    println!("  {} - exports tasks to other applications", "export".bold().green());
+    println!("  {} - imports tasks from other applications", "import".bold().green());
+    println!("  {} - upgrades stored files to the current format", "migrate".bold().grey());
+    println!("  {} - moves tasks to another storage backend", "backend".bold().grey());
+    println!("  {} - lists and restores backups of tasks", "backup".bold().grey());
+    println!("  {} - changes settings", "config".bold().grey());
     println!("  {} - prints this help message", "help".bold().grey());
     println!("  {} - clears the screen", "clear".bold().yellow());
     println!("  {} - exits the program", "exit".bold().magenta());
     println!();
 
-    let command = get_user_input("Enter command");
-    let command_enum = SupportedCommand::from_str(&command.to_lowercase());
-
-    if command_enum.is_err() {
-        println!("Invalid command: {}", command);
+    let commands: Vec<String> = SupportedCommand::all().iter().map(|c| c.to_string()).collect();
+    loop {
+        println!("Enter command");
+        // Ctrl-C, Ctrl-D and the end of piped input exit like the exit command
+        let command_line = match line_editor::read_line(commands.clone(), true) {
+            Some(command_line) => command_line,
+            None => return (SupportedCommand::Exit, Vec::new()),
+        };
+        let mut words = match command_line::split_words(&command_line) {
+            Ok(words) => words,
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
 
-        return command_selection();
+        let command = if words.is_empty() { String::new() } else { words.remove(0) };
+        match SupportedCommand::from_str(&command.to_lowercase()) {
+            Ok(command) => return (command, words),
+            Err(_) => {
+                println!("Invalid command: {}", command);
+                println!();
+            }
+        }
     }
-
-    command_enum.unwrap()
 }
 
 fn get_user_input(prompt_text: &str) -> String {
+    get_user_input_with_completions(prompt_text, Vec::new())
+}
+
+// Same as get_user_input, pressing tab completes the input to one of completions.
+// Ctrl-C and Ctrl-D give an empty input, which cancels most prompts.
+fn get_user_input_with_completions(prompt_text: &str, completions: Vec<String>) -> String {
     println!("{}", prompt_text);
-    let mut input = String::new();
-    print!("> ");
-    io::stdout().flush().expect("Failed to flush");
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
+    line_editor::read_line(completions, false).unwrap_or_default()
+}
+
+fn task_ids<'a>(tasks: impl Iterator<Item = &'a Task>) -> Vec<String> {
+    tasks.map(|t| t.id.to_string()).collect()
+}
+
+// Task ids given as arguments, or the one entered at the prompt when there are none
+fn get_task_ids(arguments: &[String], prompt_text: &str, completions: Vec<String>) -> Option<Vec<usize>> {
+    let task_ids = if arguments.is_empty() {
+        let input = get_user_input_with_completions(prompt_text, completions);
+        let words: Vec<String> = input.split_whitespace().map(|word| word.to_string()).collect();
+        command_line::parse_task_ids(&words)
+    } else {
+        command_line::parse_task_ids(arguments)
+    };
 
-    input.trim().to_string()
+    match task_ids {
+        Ok(task_ids) => Some(task_ids),
+        Err(e) => {
+            println!("{}", e);
+            press_enter();
+            None
+        }
+    }
 }
 
-fn add_task(list_of_tasks: &mut TaskList) {
-    let mut title;
+fn get_task_title() -> String {
     loop {
-        title = get_user_input("Enter title for task");
+        let title = get_user_input("Enter title for task");
         println!();
 
         if title.is_empty() {
             println!("Title cannot be empty");
             println!();
-            continue;
         } else {
-            break;
+            return title;
         }
     }
+}
+
+fn add_task(list_of_tasks: &mut TaskList, arguments: &[String]) {
+    if !arguments.is_empty() {
+        add_task_from_arguments(list_of_tasks, arguments);
+        press_enter();
+        return;
+    }
 
+    let title = get_task_title();
+
+    list_of_tasks.begin_operation();
     let task_id = list_of_tasks.add_task(title);
 
     let description = get_user_input("Enter a description for the task:");
@@ -131,109 +329,443 @@ fn add_task(list_of_tasks: &mut TaskList) {
     }
 
     loop {
-        let mut due_date =
-            get_user_input("Enter a due date and time for the task (dd.mm.YYYY HH:MM):");
+        let due_date = get_user_input(&format!(
+            "Enter a due date for the task ({}):",
+            command_line::DUE_DATE_FORMATS
+        ));
         println!();
 
         if due_date.is_empty() {
             break;
         }
 
-        due_date += ":00";
-
-        let parsed_date = NaiveDateTimeWrapper::parse_from_str(&due_date, "%d.%m.%Y %H:%M:%S");
-
-        match parsed_date {
-            Ok(_) => {
-                list_of_tasks.update_task_due_date(task_id, parsed_date.unwrap());
+        match command_line::parse_due_date(&due_date) {
+            Ok(due_date) => {
+                list_of_tasks.update_task_due_date(task_id, due_date);
                 break;
             }
-            Err(_) => {
-                println!("Invalid date format, please try again");
-                println!("{:?}", parsed_date.unwrap_err());
+            Err(e) => {
+                println!("{}, please try again", e);
                 println!();
             }
         }
     }
+    list_of_tasks.end_operation();
 
     press_enter();
 }
 
-fn list_tasks(list_of_tasks: &TaskList) {
+// Adds a task from a line like `add "Fix login" due:fri +auth`, only the title
+// is asked for when it's missing
+fn add_task_from_arguments(list_of_tasks: &mut TaskList, arguments: &[String]) {
+    let mut arguments = match TaskArguments::parse(arguments) {
+        Ok(arguments) => arguments,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    // A new task has no due date to shift, due:+2d is counted from now instead
+    if let (None, Some(shift)) = (arguments.due_date, arguments.due_date_shift) {
+        match command_line::shift_due_date(NaiveDateTimeWrapper::now(), shift) {
+            Ok(due_date) => arguments.due_date = Some(due_date),
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        }
+    }
+
+    let title = match &arguments.title {
+        Some(title) if !title.is_empty() => title.clone(),
+        _ if !arguments.words.is_empty() => arguments.words.join(" "),
+        _ => get_task_title(),
+    };
+
+    list_of_tasks.begin_operation();
+    let task_id = list_of_tasks.add_task(title.clone());
+    apply_task_arguments(list_of_tasks, task_id, &arguments);
+    list_of_tasks.end_operation();
+
+    println!("Task {} - {} added", task_id, title);
+}
+
+fn apply_task_arguments(list_of_tasks: &mut TaskList, task_id: usize, arguments: &TaskArguments) {
+    let task = match list_of_tasks.get_task_by_id(task_id) {
+        Some(task) => task.clone(),
+        None => return,
+    };
+
+    if let Some(title) = &arguments.title {
+        list_of_tasks.update_task_title(task_id, title.clone());
+    }
+    if let Some(description) = &arguments.description {
+        list_of_tasks.update_task_description(task_id, description.clone());
+    }
+    // Shifts out of range are refused before anything is applied
+    if let Ok(Some(due_date)) = arguments.apply_due_date(task.due_date) {
+        list_of_tasks.update_task_due_date(task_id, due_date);
+    }
+    if let Some(status) = &arguments.status {
+        list_of_tasks.update_task_status(task_id, status.clone());
+    }
+    if let Some(priority) = arguments.priority {
+        list_of_tasks.update_task_priority(task_id, priority);
+    }
+    list_of_tasks.update_task_tags(task_id, arguments.apply_tags(&task.tags));
+}
+
+// Columns given with --columns on the command line override the configured ones
+fn parse_columns_argument() -> Option<Vec<Column>> {
+    let args: Vec<String> = std::env::args().collect();
+    let position = args.iter().position(|arg| arg == "--columns")?;
+
+    let columns = match args.get(position + 1) {
+        Some(columns) => Column::parse_list(columns),
+        None => Err("Missing list of columns after --columns".to_string()),
+    };
+
+    match columns {
+        Ok(columns) if !columns.is_empty() => Some(columns),
+        Ok(_) => None,
+        Err(e) => {
+            println!("{}", e);
+            None
+        }
+    }
+}
+
+// Lists all tasks, or only the ones matching a query like `list @this-week tag:api`
+fn list_tasks(
+    list_of_tasks: &TaskList,
+    config: &Configuration,
+    columns: Option<&[Column]>,
+    arguments: &[String],
+) {
     println!("{}", CLEAR_SCREEN);
 
-    if list_of_tasks.tasks.is_empty() {
+    let filter = match parse_filter(arguments, &config.views) {
+        Ok(filter) => filter,
+        Err(e) => {
+            println!("{}", e);
+            press_enter();
+            return;
+        }
+    };
+
+    let tasks: Vec<&Task> = list_of_tasks
+        .active_tasks()
+        .filter(|task| filter.as_ref().is_none_or(|filter| filter.matches(task)))
+        .collect();
+    if tasks.is_empty() {
         println!("No tasks to list");
         press_enter();
         return;
     }
 
+    if config.list_layout == ListLayout::Table {
+        let columns = columns.unwrap_or(&config.list_columns);
+        table::print_tasks_table(&tasks, columns, table::terminal_width(), config.wrap_list_values);
+        println!();
+        press_enter();
+        return;
+    }
+
     println!("List of tasks:");
     println!();
-    for task in &list_of_tasks.tasks {
+    for task in tasks {
         println!("  {} - {}", task.id, task.title);
-        if task.description.is_some() {
-            println!("    Description: {}", task.description.as_ref().unwrap());
+        if let Some(description) = &task.description {
+            println!("    Description: {}", description);
+        }
+        if let Some(due_date) = &task.due_date {
+            println!("    Due date: {}", due_date);
+        }
+        println!("    Status: {}", task.status);
+        if let Some(priority) = task.priority {
+            println!("    Priority: {}", priority);
+        }
+        if !task.tags.is_empty() {
+            println!("    Tags: +{}", task.tags.join(" +"));
+        }
+        println!();
+    }
+    press_enter();
+}
+
+// Searches all tasks, including the ones in trash and the archive
+fn search_tasks(list_of_tasks: &TaskList, arguments: &[String]) {
+    let words = if arguments.is_empty() {
+        let input = get_user_input("Enter words, \"phrases\" or a /regular expression/ to search for");
+        match command_line::split_words(&input) {
+            Ok(words) => words,
+            Err(e) => {
+                println!("{}", e);
+                press_enter();
+                return;
+            }
+        }
+    } else {
+        arguments.to_vec()
+    };
+
+    let query = match search::Query::parse(&words) {
+        Ok(query) => query,
+        Err(e) => {
+            println!("{}", e);
+            press_enter();
+            return;
         }
-        if task.due_date.is_some() {
-            println!("    Due date: {}", task.due_date.as_ref().unwrap());
+    };
+
+    println!("{}", CLEAR_SCREEN);
+    let all_tasks = list_of_tasks.tasks.iter().chain(list_of_tasks.archived_tasks.iter());
+    let results = search::search(all_tasks, &query);
+    if results.is_empty() {
+        println!("No tasks found");
+        press_enter();
+        return;
+    }
+
+    println!("Found {} tasks:", results.len());
+    println!();
+    for result in results {
+        let task = result.task;
+        let location = if task.is_deleted() {
+            " (in trash)"
+        } else if list_of_tasks.archived_tasks.iter().any(|t| t.id == task.id) {
+            " (archived)"
+        } else {
+            ""
+        };
+
+        println!("  {} - {}{}", task.id, query.highlight(&task.title), location);
+        if let Some(description) = &task.description {
+            println!("    Description: {}", query.highlight(description));
         }
-        println!("    Status: {}", task.status.to_string());
+        println!("    Status: {}", task.status);
         println!();
     }
     press_enter();
 }
 
-fn update_task(list_of_tasks: &mut TaskList) {
-    if list_of_tasks.tasks.is_empty() {
+// Accepts `update 5 status:completed`, `update 3,5,8-12 due:+2d` or
+// `update status:completed tag:old set +old`, anything missing is asked for
+fn update_task(list_of_tasks: &mut TaskList, arguments: &[String], views: &BTreeMap<String, String>) {
+    if list_of_tasks.active_tasks().next().is_none() {
         println!("No tasks to update");
         press_enter();
         return;
     }
-    let task_id_input = get_user_input("Enter task Id to update");
-    // convert task_id to usize
-    let task_id_input = task_id_input.parse::<usize>();
-    let task_id = match task_id_input {
-        Ok(parsed_task_id) => parsed_task_id,
-        Err(_) => {
-            println!("Invalid task");
+
+    // Without `set` the changes follow the task ids, filters need `set` to tell them apart
+    let (selector_words, change_words) = match arguments.iter().position(|word| word == "set") {
+        Some(position) => (&arguments[..position], &arguments[position + 1..]),
+        None => {
+            let ids_end = arguments
+                .iter()
+                .position(|word| !command_line::is_task_id_list(word))
+                .unwrap_or(arguments.len());
+            arguments.split_at(ids_end)
+        }
+    };
+
+    let mut changes = match TaskArguments::parse(change_words) {
+        Ok(changes) if changes.words.is_empty() => changes,
+        Ok(changes) => {
+            println!("Unknown arguments: {}", changes.words.join(" "));
+            press_enter();
+            return;
+        }
+        Err(e) => {
+            println!("{}", e);
+            press_enter();
+            return;
+        }
+    };
+
+    let (selector, task_ids) = match select_tasks(
+        list_of_tasks,
+        selector_words,
+        "Enter task Ids or filters to update (e.g. 3,5,8-12 or status:completed tag:old)",
+        views,
+    ) {
+        Some(selected) => selected,
+        None => return,
+    };
+
+    if !changes.has_changes() {
+        if task_ids.len() == 1 && !selector.is_filter() {
+            update_task_fields(list_of_tasks, task_ids[0]);
+            press_enter();
+            return;
+        }
+
+        let change_input = get_user_input("Enter changes (e.g. status:completed +tag -tag due:+2d)");
+        println!();
+        changes = match command_line::split_words(&change_input).and_then(|words| TaskArguments::parse(&words)) {
+            Ok(changes) if changes.has_changes() => changes,
+            Ok(_) => {
+                println!("Nothing to change");
+                press_enter();
+                return;
+            }
+            Err(e) => {
+                println!("{}", e);
+                press_enter();
+                return;
+            }
+        };
+    }
+
+    // Each task as it will be after the change, for the tasks that change
+    let mut updates = Vec::new();
+    for task in task_ids.iter().filter_map(|id| list_of_tasks.get_task_by_id(*id)) {
+        match changes.apply_to(task) {
+            Ok(updated_task) if updated_task != *task => updates.push((task.clone(), updated_task)),
+            Ok(_) => {}
+            Err(e) => {
+                println!("{}", e);
+                press_enter();
+                return;
+            }
+        }
+    }
+    if updates.is_empty() {
+        println!("Nothing to change");
+        press_enter();
+        return;
+    }
+
+    if task_ids.len() > 1 || selector.is_filter() {
+        println!("{}", CLEAR_SCREEN);
+        preview_changes(&updates);
+        if !confirm(&format!("Apply these changes to {} tasks?", updates.len())) {
+            println!("No tasks were updated");
             press_enter();
             return;
         }
+    }
+
+    list_of_tasks.begin_operation();
+    for (task, _) in &updates {
+        apply_task_arguments(list_of_tasks, task.id, &changes);
+    }
+    list_of_tasks.end_operation();
+    println!("Updated {} tasks", updates.len());
+
+    press_enter();
+}
+
+// Active tasks picked by ids, ranges or filters given as arguments, or entered
+// at the prompt when there are none
+fn select_tasks(
+    list_of_tasks: &TaskList,
+    arguments: &[String],
+    prompt_text: &str,
+    views: &BTreeMap<String, String>,
+) -> Option<(TaskSelector, Vec<usize>)> {
+    let selector = if arguments.is_empty() {
+        let input = get_user_input_with_completions(prompt_text, task_ids(list_of_tasks.active_tasks()));
+        println!();
+        command_line::split_words(&input).and_then(|words| TaskSelector::parse(&words, views))
+    } else {
+        TaskSelector::parse(arguments, views)
+    };
+
+    let selector = match selector {
+        Ok(selector) => selector,
+        Err(e) => {
+            println!("{}", e);
+            press_enter();
+            return None;
+        }
     };
 
+    let (selected, missing) = selector.select(list_of_tasks.active_tasks());
+    if !missing.is_empty() {
+        let missing: Vec<String> = missing.iter().map(|id| id.to_string()).collect();
+        println!("Skipping Ids that are not in the list: {}", missing.join(", "));
+    }
+    if selected.is_empty() {
+        println!("No tasks selected");
+        press_enter();
+        return None;
+    }
+
+    let task_ids = selected.iter().map(|task| task.id).collect();
+    Some((selector, task_ids))
+}
+
+// Prints what changes for each task, given as it is before and after the change
+fn preview_changes(updates: &[(Task, Task)]) {
+    for (task, updated_task) in updates {
+        let change = Change::Updated {
+            before: task.clone(),
+            after: updated_task.clone(),
+        };
+
+        println!("  {} - {}", task.id, task.title);
+        for event in Event::from_change(&change, NaiveDateTimeWrapper::now()) {
+            println!("    {}", event);
+        }
+    }
+    println!();
+}
+
+fn confirm(prompt_text: &str) -> bool {
+    let confirmation = get_user_input(&format!("{} (y/n)", prompt_text));
+    println!();
+
+    confirmation.to_lowercase() == "y"
+}
+
+fn update_task_fields(list_of_tasks: &mut TaskList, task_id: usize) {
     println!("{}", CLEAR_SCREEN);
     // print task and details
     println!(
         "Task: {}",
-        list_of_tasks.get_task_by_id(task_id).unwrap().title
+        list_of_tasks.get_active_task_by_id(task_id).unwrap().title
     );
     println!(
         "  Description: {}",
         list_of_tasks
-            .get_task_by_id(task_id)
+            .get_active_task_by_id(task_id)
             .unwrap()
             .description
             .clone()
             .unwrap_or("Not provided".to_string())
     );
-    let due_date = list_of_tasks.get_task_by_id(task_id).unwrap().due_date;
-    if due_date.is_some() {
-        println!("  Due date: {}", due_date.unwrap());
+    let due_date = list_of_tasks.get_active_task_by_id(task_id).unwrap().due_date;
+    if let Some(due_date) = due_date {
+        println!("  Due date: {}", due_date);
     } else {
         println!("  Due date: Not provided");
     }
     println!(
         "  Status: {}",
         list_of_tasks
-            .get_task_by_id(task_id)
+            .get_active_task_by_id(task_id)
             .unwrap()
             .status
-            .to_string()
     );
+    if let Some(priority) = list_of_tasks.get_active_task_by_id(task_id).unwrap().priority {
+        println!("  Priority: {}", priority);
+    }
+    let tags = &list_of_tasks.get_active_task_by_id(task_id).unwrap().tags;
+    if !tags.is_empty() {
+        println!("  Tags: +{}", tags.join(" +"));
+    }
 
     loop {
-        let field_to_update = get_user_input("Choose field to update").to_lowercase();
+        let fields = vec![
+            "description".to_string(),
+            "due date".to_string(),
+            "status".to_string(),
+            "priority".to_string(),
+            "tags".to_string(),
+        ];
+        let field_to_update = get_user_input_with_completions("Choose field to update", fields).to_lowercase();
         println!();
 
         match field_to_update.as_str() {
@@ -245,20 +777,18 @@ fn update_task(list_of_tasks: &mut TaskList) {
             }
             "due date" => {
                 loop {
-                    let new_date_string =
-                        get_user_input("Enter new due date and time (dd.mm.YYYY HH:MM)");
+                    let new_date_string = get_user_input(&format!(
+                        "Enter new due date ({})",
+                        command_line::DUE_DATE_FORMATS
+                    ));
                     println!();
-                    let new_date_string = new_date_string + ":00";
-                    let parsed_date =
-                        NaiveDateTimeWrapper::parse_from_str(&new_date_string, "%d.%m.%Y %H:%M:%S");
-                    match parsed_date {
-                        Ok(_) => {
-                            list_of_tasks.update_task_due_date(task_id, parsed_date.unwrap());
+                    match command_line::parse_due_date(&new_date_string) {
+                        Ok(due_date) => {
+                            list_of_tasks.update_task_due_date(task_id, due_date);
                             break;
                         }
-                        Err(_) => {
-                            println!("Invalid date format, please try again");
-                            println!("{:?}", parsed_date.unwrap_err());
+                        Err(e) => {
+                            println!("{}, please try again", e);
                             println!();
                             continue;
                         }
@@ -273,20 +803,41 @@ fn update_task(list_of_tasks: &mut TaskList) {
                     TaskStatus::InProgress.to_string(),
                     TaskStatus::Completed.to_string()
                 );
-                let new_status = get_user_input("Enter new status");
+                let statuses = TaskStatus::all().iter().map(|s| s.to_string()).collect();
+                let new_status = get_user_input_with_completions("Enter new status", statuses);
                 println!();
-                let new_status = new_status.parse::<TaskStatus>();
-                match new_status {
+                match command_line::parse_status(&new_status) {
                     Ok(parsed_status) => {
                         list_of_tasks.update_task_status(task_id, parsed_status);
                         break;
                     }
-                    Err(_) => {
-                        println!("Invalid status, please try again");
+                    Err(e) => {
+                        println!("{}, please try again", e);
+                        continue;
+                    }
+                }
+            }
+            "priority" => {
+                let priorities = Priority::all().iter().map(|p| p.to_string()).chain(["none".to_string()]).collect();
+                let new_priority = get_user_input_with_completions("Enter new priority (low, medium, high or none)", priorities);
+                println!();
+                match command_line::parse_priority(&new_priority) {
+                    Ok(priority) => {
+                        list_of_tasks.update_task_priority(task_id, priority);
+                        break;
+                    }
+                    Err(e) => {
+                        println!("{}, please try again", e);
                         continue;
                     }
                 }
             }
+            "tags" => {
+                let new_tags = get_user_input("Enter tags separated by spaces");
+                println!();
+                list_of_tasks.update_task_tags(task_id, task::parse_tags(&new_tags));
+                break;
+            }
             _ => {
                 println!("Invalid field to update: {}", field_to_update);
                 println!();
@@ -294,74 +845,1166 @@ fn update_task(list_of_tasks: &mut TaskList) {
             }
         }
     }
-
-    press_enter();
 }
 
-fn delete_task(list_of_tasks: &mut TaskList) {
-    if list_of_tasks.tasks.is_empty() {
+fn delete_task(list_of_tasks: &mut TaskList, arguments: &[String], views: &BTreeMap<String, String>) {
+    if list_of_tasks.active_tasks().next().is_none() {
         println!("No tasks to delete");
         press_enter();
         return;
     }
 
-    let task_id_input = get_user_input("Enter task Id to update");
-    // convert task_id to usize
-    let task_id_input = task_id_input.parse::<usize>();
-    let task_id = match task_id_input {
-        Ok(parsed_task_id) => parsed_task_id,
-        Err(_) => {
-            println!("Invalid task");
-            press_enter();
-            return;
-        }
+    let (selector, task_ids) = match select_tasks(
+        list_of_tasks,
+        arguments,
+        "Enter task Ids or filters to delete (e.g. 3,5,8-12 or status:completed tag:old)",
+        views,
+    ) {
+        Some(selected) => selected,
+        None => return,
     };
 
     println!("{}", CLEAR_SCREEN);
-    let deletion_result = list_of_tasks.delete_task(task_id);
+    if task_ids.len() > 1 || selector.is_filter() {
+        println!("Tasks to move to trash:");
+        for task in task_ids.iter().filter_map(|id| list_of_tasks.get_task_by_id(*id)) {
+            println!("  {} - {}", task.id, task.title);
+        }
+        println!();
 
-    match deletion_result {
-        Ok(_) => {
-            println!("Task deleted successfully");
+        if !confirm(&format!("Move {} tasks to trash?", task_ids.len())) {
+            println!("No tasks were deleted");
+            press_enter();
+            return;
         }
-        Err(_) => {
-            println!("Failed to delete task");
+    }
+
+    list_of_tasks.begin_operation();
+    for task_id in task_ids {
+        match list_of_tasks.delete_task(task_id) {
+            Some(task) => println!("Task {} - {} moved to trash", task.id, task.title),
+            None => println!("Failed to delete task {}", task_id),
         }
     }
+    list_of_tasks.end_operation();
 
     press_enter();
 }
 
-fn help() {
+fn list_trash(list_of_tasks: &TaskList) {
     println!("{}", CLEAR_SCREEN);
-    println!("To-Do app by @realsnack");
-    println!("https://github.com/Realsnack/rust-to-do-app");
-    println!();
-    println!();
-    println!("You can use these commands:");
-    println!("  {} - Allows you to add a new task", "add".bold().green());
-    println!("    The application will first ask you to enter a title for the task");
-    println!("    Then it will ask you to enter a description for the task - if you don't want to enter a description, just press enter");
-    println!("    Finally it will ask you to enter a due date for the task - if you don't want to enter a due date, just press enter");
-    println!("    The task is automatically created with status 'Not started'");
-    println!("  {} - list all tasks", "list".bold().cyan());
-    println!("    This will print a list of all tasks");
-    println!("    Each task will have an ID, title, description, and due date. If a task doesn't have a description or due date, it will be marked as such");
-    println!(
-        "  {} - allows you to update a task",
-        "update".bold().yellow()
-    );
-    println!("    This will allow you to update a task");
-    println!("    You will first be asked to enter the ID of the task you want to update. Then you will be asked to select which field you want to update. Finally you will be asked to enter the new value for the field");
-    println!("  {} - deletes a task", "delete".bold().red());
-    println!("    This will allow you to delete a task");
-    println!("    You will first be asked to enter the ID of the task you want to delete, then a confirmation message will be displayed. If you confirm, the task will be deleted");
-    println!("  {} - prints this help message", "help".bold().grey());
-    println!("  {} - exits the program", "exit".bold().magenta());
+
+    if list_of_tasks.trashed_tasks().next().is_none() {
+        println!("Trash is empty");
+        press_enter();
+        return;
+    }
+
+    println!("Tasks in trash:");
     println!();
+    for task in list_of_tasks.trashed_tasks() {
+        println!("  {} - {}", task.id, task.title);
+        if let Some(deleted_at) = &task.deleted_at {
+            println!("    Deleted: {}", deleted_at);
+        }
+        println!();
+    }
     press_enter();
+}
 
-    println!("{}", CLEAR_SCREEN);
+fn restore_task(list_of_tasks: &mut TaskList, arguments: &[String]) {
+    if list_of_tasks.trashed_tasks().next().is_none() {
+        println!("Trash is empty");
+        press_enter();
+        return;
+    }
+
+    let task_ids = match get_task_ids(
+        arguments,
+        "Enter task Id to restore",
+        task_ids(list_of_tasks.trashed_tasks()),
+    ) {
+        Some(task_ids) => task_ids,
+        None => return,
+    };
+
+    list_of_tasks.begin_operation();
+    for task_id in task_ids {
+        match list_of_tasks.restore_task(task_id) {
+            Some(task) => println!("Task {} - {} restored", task.id, task.title),
+            None => println!("Task {} is not in trash", task_id),
+        }
+    }
+    list_of_tasks.end_operation();
+
+    press_enter();
+}
+
+fn empty_trash(list_of_tasks: &mut TaskList) {
+    let trashed_count = list_of_tasks.trashed_tasks().count();
+    if trashed_count == 0 {
+        println!("Trash is empty");
+        press_enter();
+        return;
+    }
+
+    let confirmation = get_user_input(&format!(
+        "Permanently delete {} tasks in trash? (y/n)",
+        trashed_count
+    ));
+    println!();
+
+    if confirmation.to_lowercase() == "y" {
+        let purged = list_of_tasks.empty_trash();
+        println!("Permanently deleted {} tasks", purged);
+    } else {
+        println!("Trash was not emptied");
+    }
+
+    press_enter();
+}
+
+fn archive_tasks(list_of_tasks: &mut TaskList, arguments: &[String]) {
+    let mut arguments = arguments.to_vec();
+    if arguments.is_empty() {
+        let completed_tasks = list_of_tasks.active_tasks().filter(|t| t.status == TaskStatus::Completed);
+        let task_id_input = get_user_input_with_completions(
+            "Enter task Id to archive (leave empty to archive all completed tasks)",
+            task_ids(completed_tasks),
+        );
+        println!();
+
+        if task_id_input.is_empty() {
+            let archived = list_of_tasks.archive_completed_tasks();
+            println!("Archived {} completed tasks", archived);
+            press_enter();
+            return;
+        }
+        arguments.push(task_id_input);
+    }
+
+    let task_ids = match command_line::parse_task_ids(&arguments) {
+        Ok(task_ids) => task_ids,
+        Err(e) => {
+            println!("{}", e);
+            press_enter();
+            return;
+        }
+    };
+
+    list_of_tasks.begin_operation();
+    for task_id in task_ids {
+        match list_of_tasks.archive_task(task_id) {
+            Some(task) => println!("Task {} - {} archived", task.id, task.title),
+            None => println!("Task {} is not a completed task", task_id),
+        }
+    }
+    list_of_tasks.end_operation();
+
+    press_enter();
+}
+
+fn search_archive(list_of_tasks: &TaskList) {
+    let search_text = get_user_input("Enter text to search for (leave empty to list all archived tasks)");
+    println!("{}", CLEAR_SCREEN);
+
+    let found_tasks = list_of_tasks.search_archive(&search_text);
+    if found_tasks.is_empty() {
+        println!("No archived tasks found");
+        press_enter();
+        return;
+    }
+
+    println!("Archived tasks:");
+    println!();
+    for task in found_tasks {
+        println!("  {} - {}", task.id, task.title);
+        if let Some(description) = &task.description {
+            println!("    Description: {}", description);
+        }
+        if let Some(completed_at) = &task.completed_at {
+            println!("    Completed: {}", completed_at);
+        }
+        println!();
+    }
+    press_enter();
+}
+
+fn unarchive_task(list_of_tasks: &mut TaskList, arguments: &[String]) {
+    let task_ids = match get_task_ids(
+        arguments,
+        "Enter task Id to unarchive",
+        task_ids(list_of_tasks.archived_tasks.iter()),
+    ) {
+        Some(task_ids) => task_ids,
+        None => return,
+    };
+
+    list_of_tasks.begin_operation();
+    for task_id in task_ids {
+        match list_of_tasks.unarchive_task(task_id) {
+            Some(task) => println!("Task {} - {} moved back to the list", task.id, task.title),
+            None => println!("Task {} is not archived", task_id),
+        }
+    }
+    list_of_tasks.end_operation();
+
+    press_enter();
+}
+
+// No arguments means no filter
+fn parse_filter(arguments: &[String], views: &BTreeMap<String, String>) -> Result<Option<Filter>, String> {
+    if arguments.is_empty() {
+        return Ok(None);
+    }
+
+    Filter::parse(arguments, views).map(Some)
+}
+
+// Statistics of active and archived tasks, or only of the ones matching a
+// query like `stats tag:api`
+fn show_stats(list_of_tasks: &TaskList, arguments: &[String], views: &BTreeMap<String, String>) {
+    println!("{}", CLEAR_SCREEN);
+
+    let filter = match parse_filter(arguments, views) {
+        Ok(filter) => filter,
+        Err(e) => {
+            println!("{}", e);
+            press_enter();
+            return;
+        }
+    };
+
+    let tasks: Vec<&Task> = list_of_tasks
+        .active_tasks()
+        .chain(list_of_tasks.archived_tasks.iter())
+        .filter(|task| filter.as_ref().is_none_or(|filter| filter.matches(task)))
+        .collect();
+    if tasks.is_empty() {
+        println!("No tasks to report on");
+        press_enter();
+        return;
+    }
+
+    let creation_times = match list_of_tasks.storage().load_events() {
+        Ok(events) => event_log::creation_times(events),
+        Err(e) => {
+            println!("Could not read creation times from the event log: {}", e);
+            HashMap::new()
+        }
+    };
+
+    if !arguments.is_empty() {
+        println!("Statistics for {} tasks matching {}", tasks.len(), command_line::join_words(arguments));
+        println!();
+    }
+    stats::print_stats(&tasks, &creation_times);
+    println!();
+    press_enter();
+}
+
+// `export ics calendar.ics`, the format and file are asked for when not given
+fn export_tasks(list_of_tasks: &TaskList, list_id: u32, arguments: &[String]) {
+    println!("{}", CLEAR_SCREEN);
+
+    let (flags, arguments): (Vec<String>, Vec<String>) =
+        arguments.iter().cloned().partition(|argument| argument.starts_with("--"));
+    let mut options = ExportOptions {
+        list_id,
+        ..ExportOptions::default()
+    };
+    for flag in &flags {
+        match flag.as_str() {
+            "--by-project" => options.grouping = markdown::Grouping::Project,
+            _ => {
+                println!("Unknown option: {}", flag);
+                press_enter();
+                return;
+            }
+        }
+    }
+
+    let format_name = match arguments.first() {
+        Some(format_name) => format_name.clone(),
+        None => {
+            let formats = ExportFormat::all().iter().map(|f| f.to_string()).collect();
+            get_user_input_with_completions("Enter format to export to (ics, todo.txt, markdown, taskwarrior, html)", formats)
+        }
+    };
+    let format = match ExportFormat::from_str(&format_name) {
+        Ok(format) => format,
+        Err(_) => {
+            println!("Invalid export format: {}", format_name);
+            press_enter();
+            return;
+        }
+    };
+
+    let path = match arguments.get(1) {
+        Some(path) => path.clone(),
+        None if arguments.is_empty() => {
+            let prompt = format!("Enter file to export to, leave empty for {}", format.default_file_name());
+            get_user_input(&prompt)
+        }
+        None => String::new(),
+    };
+    let path = if path.is_empty() { format.default_file_name().to_string() } else { path };
+
+    if export::is_app_file(&path) {
+        println!("{} holds the app's own data, choose another file", path);
+        press_enter();
+        return;
+    }
+    if std::path::Path::new(&path).exists() && !confirm(&format!("{} already exists, overwrite it?", path)) {
+        println!("Nothing was exported");
+        press_enter();
+        return;
+    }
+
+    let tasks: Vec<&Task> = list_of_tasks.active_tasks().collect();
+    match export::export_tasks(&tasks, format, &path, &options) {
+        Ok(()) => println!("Exported {} tasks to {}", tasks.len(), path),
+        Err(e) => println!("Could not export tasks to {}: {}", path, e),
+    }
+    press_enter();
+}
+
+// `import ics invites.ics --events`, the format and file are asked for when not
+// given. Importing the same file again updates the tasks added the first time.
+fn import_tasks(list_of_tasks: &mut TaskList, list_id: u32, arguments: &[String]) {
+    println!("{}", CLEAR_SCREEN);
+
+    let (flags, mut words): (Vec<String>, Vec<String>) =
+        arguments.iter().cloned().partition(|argument| argument.starts_with("--"));
+    let mut options = ImportOptions {
+        list_id,
+        ..ImportOptions::default()
+    };
+    for flag in &flags {
+        match flag.as_str() {
+            "--events" => options.include_events = true,
+            _ => {
+                println!("Unknown option: {}", flag);
+                press_enter();
+                return;
+            }
+        }
+    }
+
+    if words.is_empty() {
+        let formats = ImportFormat::all().iter().map(|f| f.to_string()).collect();
+        words.push(get_user_input_with_completions("Enter format to import from (ics, todo.txt, markdown, taskwarrior)", formats));
+    }
+    let format = match ImportFormat::from_str(&words[0]) {
+        Ok(format) => format,
+        Err(_) => {
+            println!("Invalid import format: {}", words[0]);
+            press_enter();
+            return;
+        }
+    };
+    if words.len() < 2 {
+        words.push(get_user_input("Enter file to import from"));
+    }
+    let path = &words[1];
+
+    let imported_tasks = match import::read_tasks(format, path, &options) {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            println!("Could not import tasks from {}: {}", path, e);
+            press_enter();
+            return;
+        }
+    };
+
+    let (mut added, mut updated, mut skipped) = (0, 0, 0);
+    // The task each item was imported to, in the order of the items
+    let mut task_ids = Vec::new();
+    list_of_tasks.begin_operation();
+    for imported_task in &imported_tasks {
+        match find_imported_task(list_of_tasks, imported_task, list_id) {
+            Some(task) if task.is_deleted() || list_of_tasks.get_active_task_by_id(task.id).is_none() => {
+                // Tasks in trash or in the archive stay there
+                task_ids.push(task.id);
+                skipped += 1;
+            }
+            Some(task) => {
+                let task_id = task.id;
+                let before = task.clone();
+                apply_imported_task(list_of_tasks, task_id, imported_task, format);
+                if list_of_tasks.get_task_by_id(task_id) != Some(&before) {
+                    updated += 1;
+                }
+                task_ids.push(task_id);
+            }
+            None => {
+                let task_id = list_of_tasks.add_task(imported_task.title.clone());
+                list_of_tasks.update_task_uid(task_id, imported_task.uid.clone());
+                apply_imported_task(list_of_tasks, task_id, imported_task, format);
+                added += 1;
+                task_ids.push(task_id);
+            }
+        }
+    }
+    list_of_tasks.end_operation();
+
+    println!(
+        "Imported {} items from {}: {} added, {} updated, {} unchanged, {} skipped in trash or archive",
+        imported_tasks.len(),
+        path,
+        added,
+        updated,
+        imported_tasks.len() - added - updated - skipped,
+        skipped
+    );
+    match import::write_back(format, path, &task_ids, list_id) {
+        Ok(true) => println!("Added the ids of the imported tasks to {}", path),
+        Ok(false) => {}
+        Err(e) => println!("Could not update {}: {}", path, e),
+    }
+    press_enter();
+}
+
+// The task an item was imported to before, or the task it was exported from
+fn find_imported_task<'a>(list_of_tasks: &'a TaskList, imported_task: &Task, list_id: u32) -> Option<&'a Task> {
+    let mut tasks = list_of_tasks.tasks.iter().chain(list_of_tasks.archived_tasks.iter());
+    // Formats without UIDs keep the id of tasks exported from this list
+    if imported_task.id != 0 {
+        return tasks.find(|task| task.id == imported_task.id);
+    }
+    let uid = imported_task.uid.as_deref()?;
+
+    match ical::task_id_from_uid(uid, list_id) {
+        Some(task_id) => tasks.find(|task| task.id == task_id && task.uid.is_none()),
+        None => tasks.find(|task| task.uid.as_deref() == Some(uid)),
+    }
+}
+
+// Fields missing in the imported item were removed and are cleared, except for
+// descriptions in formats without them and creation times, which not every
+// item records
+fn apply_imported_task(list_of_tasks: &mut TaskList, task_id: usize, imported_task: &Task, format: ImportFormat) {
+    list_of_tasks.update_task_title(task_id, imported_task.title.clone());
+    match &imported_task.description {
+        Some(description) => list_of_tasks.update_task_description(task_id, description.clone()),
+        None if format.has_descriptions() => list_of_tasks.clear_task_description(task_id),
+        None => {}
+    }
+    match imported_task.due_date {
+        Some(due_date) => list_of_tasks.update_task_due_date(task_id, due_date),
+        None => list_of_tasks.clear_task_due_date(task_id),
+    }
+    let status_changed = list_of_tasks
+        .get_task_by_id(task_id)
+        .is_some_and(|task| task.status != imported_task.status);
+    if status_changed {
+        list_of_tasks.update_task_status(task_id, imported_task.status.clone());
+        if imported_task.completed_at.is_some() {
+            list_of_tasks.update_task_completed_at(task_id, imported_task.completed_at);
+        }
+    }
+    list_of_tasks.update_task_priority(task_id, imported_task.priority);
+    if imported_task.created_at.is_some() {
+        list_of_tasks.update_task_created_at(task_id, imported_task.created_at);
+    }
+    list_of_tasks.update_task_tags(task_id, imported_task.tags.clone());
+}
+
+// `migrate` upgrades stored files to the current format versions, `migrate --dry-run`
+// only shows what would change
+fn migrate_files(arguments: &[String]) {
+    let dry_run = arguments.iter().any(|argument| argument == "--dry-run");
+
+    match run_migrations(dry_run) {
+        Ok(0) => println!("All files are up to date"),
+        Ok(_) if dry_run => println!("Nothing was changed, run migrate without --dry-run to upgrade the files"),
+        Ok(_) => {}
+        Err(e) => println!("Could not upgrade stored data: {}", e),
+    }
+}
+
+// Prints what was or would be migrated, returns the number of files
+fn run_migrations(dry_run: bool) -> Result<usize, Box<dyn std::error::Error>> {
+    let reports = migration::migrate_files(dry_run)?;
+
+    for report in &reports {
+        let verb = if dry_run { "would be upgraded" } else { "was upgraded" };
+        println!(
+            "{} {} from version {} to {}",
+            report.path.clone().bold(),
+            verb,
+            report.from_version,
+            report.to_version
+        );
+        for change in &report.changes {
+            println!("  {}", change);
+        }
+        if let Some(backup_path) = &report.backup_path {
+            println!("  The previous version was copied to {}", backup_path);
+        }
+    }
+
+    Ok(reports.len())
+}
+
+// `backend` shows where tasks are stored, `backend postgres` copies everything
+// there and stores tasks in it from then on
+fn switch_backend(list_of_tasks: &mut TaskList, config: &mut Configuration, arguments: &[String]) {
+    println!("{}", CLEAR_SCREEN);
+
+    let target = match arguments.first() {
+        Some(target) => target.clone(),
+        None => {
+            println!("Tasks are stored in {}", config.persistence);
+            get_user_input("Enter the backend to move tasks to (csv, postgres), or nothing to keep it")
+        }
+    };
+    if target.is_empty() {
+        return;
+    }
+
+    let target = match SupportedPersistence::from_str(&target) {
+        Ok(target) => target,
+        Err(_) => {
+            println!("Unknown backend: {} (use csv or postgres)", target);
+            press_enter();
+            return;
+        }
+    };
+    if target == config.persistence {
+        println!("Tasks are already stored in {}", target);
+        press_enter();
+        return;
+    }
+
+    // Changes only in memory wouldn't be copied
+    if !save_tasks(list_of_tasks, config.backup_retention) {
+        println!("Nothing was copied");
+        press_enter();
+        return;
+    }
+    list_of_tasks.history.save_history(HISTORY_FILE);
+    config.task_counter = list_of_tasks.get_task_counter();
+
+    let copied = storage::storage_for(config.persistence, config.task_counter).and_then(|from| {
+        let to = storage::storage_for(target, config.task_counter)?;
+        let report = storage::copy_data(from.as_ref(), to.as_ref(), config.backup_retention)?;
+        list_of_tasks.use_storage(to)?;
+        Ok(report)
+    });
+    match copied {
+        Ok(report) => {
+            println!(
+                "Copied {} tasks, {} archived tasks, {} events and task counter {} from {} to {}, all of them were read back unchanged",
+                report.tasks, report.archived_tasks, report.events, report.task_counter, config.persistence, target
+            );
+            println!(
+                "Tasks are stored in {} from now on, {} was emptied after a backup of it was made",
+                target, config.persistence
+            );
+            config.persistence = target;
+            config.task_counter = report.task_counter;
+            list_of_tasks.update_task_counter(report.task_counter);
+            config.save_configuration(CONFIG_FILE);
+        }
+        Err(e) => println!(
+            "Could not move tasks to {}: {}. Tasks are still stored in {}",
+            target, e, config.persistence
+        ),
+    }
+    press_enter();
+}
+
+// `backup` or `backup list` lists the backups, `backup restore 20261019-143005`
+// changes all tasks back to how they were in that backup
+fn manage_backups(list_of_tasks: &mut TaskList, arguments: &[String]) {
+    println!("{}", CLEAR_SCREEN);
+
+    match arguments.first().map(|a| a.as_str()) {
+        None | Some("list") => match backup::list_backups() {
+            Ok(backups) if backups.is_empty() => println!("No backups yet, one is made before tasks are saved"),
+            Ok(backups) => {
+                println!("Backups, oldest first:");
+                for backup in backups {
+                    let contents = match backup.load() {
+                        Ok((tasks, archived_tasks)) => format!("{} tasks, {} archived", tasks.len(), archived_tasks.len()),
+                        Err(e) => format!("could not be read: {}", e),
+                    };
+                    println!("  {}  {}  {}", backup.name.bold(), backup.time.format("%d.%m.%Y %H:%M:%S"), contents);
+                }
+            }
+            Err(e) => println!("Could not list backups: {}", e),
+        },
+        Some("restore") => {
+            let name = match arguments.get(1) {
+                Some(name) => name.clone(),
+                None => get_user_input("Enter the backup to restore, e.g. 20261019-143005"),
+            };
+            let backup = match backup::find_backup(&name) {
+                Ok(Some(backup)) => backup,
+                Ok(None) => {
+                    println!("No backup named {}, see backup list", name);
+                    press_enter();
+                    return;
+                }
+                Err(e) => {
+                    println!("Could not find backup: {}", e);
+                    press_enter();
+                    return;
+                }
+            };
+
+            match backup.load() {
+                Ok((tasks, archived_tasks)) => {
+                    let changed = list_of_tasks.replace_tasks(tasks, archived_tasks);
+                    println!("Restored backup {}, {} tasks were changed", backup.name, changed);
+                    if changed > 0 {
+                        println!("Use undo to go back to the tasks from before the restore");
+                    }
+                }
+                Err(e) => println!("Could not read backup {}: {}", backup.name, e),
+            }
+        }
+        Some(other) => println!("Unknown backup command: {} (use list or restore)", other),
+    }
+
+    press_enter();
+}
+
+// `view` lists saved views, `view save this-week due<+7d` saves one and
+// `view delete this-week` removes it
+fn manage_views(config: &mut Configuration, arguments: &[String]) {
+    println!("{}", CLEAR_SCREEN);
+
+    match arguments.split_first() {
+        None => {
+            if config.views.is_empty() {
+                println!("No saved views, save one with: view save <name> <query>");
+            }
+            for (name, query) in &config.views {
+                println!("  {} - {}", format!("@{}", name).bold().cyan(), query);
+            }
+        }
+        Some((action, rest)) if action == "save" && rest.len() >= 2 => {
+            let name = rest[0].trim_start_matches('@').to_string();
+            let query = command_line::join_words(&rest[1..]);
+
+            // Validated with the view itself missing so it can't refer to itself
+            let mut other_views = config.views.clone();
+            other_views.remove(&name);
+            match Filter::parse(&rest[1..], &other_views) {
+                Ok(_) => {
+                    println!("Saved view @{}: {}", name, query);
+                    config.views.insert(name, query);
+                    config.save_configuration(CONFIG_FILE);
+                }
+                Err(e) => println!("{}", e),
+            }
+        }
+        Some((action, rest)) if action == "delete" && rest.len() == 1 => {
+            let name = rest[0].trim_start_matches('@');
+            if config.views.remove(name).is_some() {
+                println!("Deleted view @{}", name);
+                config.save_configuration(CONFIG_FILE);
+            } else {
+                println!("Unknown view: @{}", name);
+            }
+        }
+        Some(_) => println!("Usage: view, view save <name> <query> or view delete <name>"),
+    }
+
+    println!();
+    press_enter();
+}
+
+fn update_configuration(config: &mut Configuration) {
+    println!("{}", CLEAR_SCREEN);
+    println!("Settings:");
+    println!("  Storage backend: {} (change it with backend)", config.persistence);
+    println!(
+        "  Trash retention: {}",
+        config
+            .trash_retention_days
+            .map(|days| format!("{} days", days))
+            .unwrap_or("Keep forever".to_string())
+    );
+    println!(
+        "  Auto archive: {}",
+        config
+            .auto_archive_days
+            .map(|days| format!("{} days after completion", days))
+            .unwrap_or("Off".to_string())
+    );
+    println!("  List layout: {}", config.list_layout);
+    let list_columns: Vec<String> = config.list_columns.iter().map(|c| c.to_string()).collect();
+    println!("  List columns: {}", list_columns.join(","));
+    println!(
+        "  Wrap list values: {}",
+        if config.wrap_list_values { "yes" } else { "no" }
+    );
+    println!("  Backup retention: {} backups", config.backup_retention);
+    println!();
+
+    loop {
+        let settings = ["trash retention", "auto archive", "list layout", "list columns", "wrap list values", "backup retention"];
+        let setting_to_update = get_user_input_with_completions(
+            "Choose setting to update (leave empty to go back)",
+            settings.iter().map(|s| s.to_string()).collect(),
+        )
+        .to_lowercase();
+        println!();
+
+        match setting_to_update.as_str() {
+            "" => break,
+            "trash retention" => {
+                let days = get_user_input("Enter number of days to keep tasks in trash (leave empty to keep forever)");
+                println!();
+
+                if days.is_empty() {
+                    config.trash_retention_days = None;
+                    break;
+                }
+
+                match days.parse::<u32>() {
+                    Ok(days) => {
+                        config.trash_retention_days = Some(days);
+                        break;
+                    }
+                    Err(_) => {
+                        println!("Invalid number of days, please try again");
+                        continue;
+                    }
+                }
+            }
+            "auto archive" => {
+                let days = get_user_input("Enter number of days after which completed tasks are archived (leave empty to turn off)");
+                println!();
+
+                if days.is_empty() {
+                    config.auto_archive_days = None;
+                    break;
+                }
+
+                match days.parse::<u32>() {
+                    Ok(days) => {
+                        config.auto_archive_days = Some(days);
+                        break;
+                    }
+                    Err(_) => {
+                        println!("Invalid number of days, please try again");
+                        continue;
+                    }
+                }
+            }
+            "list layout" => {
+                let layout = get_user_input("Enter list layout (table or detailed)");
+                println!();
+
+                match layout.parse::<ListLayout>() {
+                    Ok(layout) => {
+                        config.list_layout = layout;
+                        break;
+                    }
+                    Err(_) => {
+                        println!("Invalid list layout, please try again");
+                        continue;
+                    }
+                }
+            }
+            "list columns" => {
+                let available_columns: Vec<String> = Column::all().iter().map(|c| c.to_string()).collect();
+                println!("Available columns: {}", available_columns.join(", "));
+                let columns = get_user_input("Enter columns separated by commas");
+                println!();
+
+                match Column::parse_list(&columns) {
+                    Ok(columns) if !columns.is_empty() => {
+                        config.list_columns = columns;
+                        break;
+                    }
+                    Ok(_) => {
+                        println!("At least one column is needed, please try again");
+                        continue;
+                    }
+                    Err(e) => {
+                        println!("{}, please try again", e);
+                        continue;
+                    }
+                }
+            }
+            "wrap list values" => {
+                let wrap = get_user_input("Wrap long titles and descriptions instead of truncating them? (y/n)");
+                println!();
+                config.wrap_list_values = wrap.to_lowercase() == "y";
+                break;
+            }
+            "backup retention" => {
+                let count = get_user_input("Enter number of backups to keep (0 to make no backups)");
+                println!();
+
+                match count.parse::<usize>() {
+                    Ok(count) => {
+                        config.backup_retention = count;
+                        break;
+                    }
+                    Err(_) => {
+                        println!("Invalid number of backups, please try again");
+                        continue;
+                    }
+                }
+            }
+            _ => {
+                println!("Invalid setting: {}", setting_to_update);
+                println!();
+                continue;
+            }
+        }
+    }
+
+    config.save_configuration(CONFIG_FILE);
+}
+
+fn undo(list_of_tasks: &mut TaskList) {
+    match list_of_tasks.undo() {
+        Some(operation) => println!("Undone: {}", operation),
+        None => println!("Nothing to undo"),
+    }
+
+    press_enter();
+}
+
+fn redo(list_of_tasks: &mut TaskList) {
+    match list_of_tasks.redo() {
+        Some(operation) => println!("Redone: {}", operation),
+        None => println!("Nothing to redo"),
+    }
+
+    press_enter();
+}
+
+fn task_history(list_of_tasks: &TaskList, arguments: &[String]) {
+    let known_tasks = list_of_tasks.tasks.iter().chain(list_of_tasks.archived_tasks.iter());
+    let task_ids = match get_task_ids(arguments, "Enter task Id to show history for", task_ids(known_tasks)) {
+        Some(task_ids) => task_ids,
+        None => return,
+    };
+
+    println!("{}", CLEAR_SCREEN);
+    for task_id in task_ids {
+        match list_of_tasks.storage().load_events().map(|events| event_log::task_history(events, task_id)) {
+            Ok(events) if events.is_empty() => {
+                println!("No recorded changes for task {}", task_id);
+            }
+            Ok(events) => {
+                println!("History of task {}:", task_id);
+                println!();
+                for event in events {
+                    println!("  {} - {}", event.timestamp, event);
+                }
+            }
+            Err(e) => println!("Error reading event log: {}", e),
+        }
+        println!();
+    }
+
+    press_enter();
+}
+
+fn list_tasks_as_of(list_of_tasks: &TaskList) {
+    let date_input = get_user_input("Enter a date and time (dd.mm.YYYY HH:MM)") + ":00";
+    let as_of = match NaiveDateTimeWrapper::parse_from_str(&date_input, "%d.%m.%Y %H:%M:%S") {
+        Ok(as_of) => as_of,
+        Err(e) => {
+            println!("Invalid date format");
+            println!("{:?}", e);
+            press_enter();
+            return;
+        }
+    };
+
+    println!("{}", CLEAR_SCREEN);
+    let tasks = match list_of_tasks.storage().replay(Some(as_of.0)) {
+        Ok((tasks, _)) => tasks,
+        Err(e) => {
+            println!("Could not rebuild tasks: {}", e);
+            press_enter();
+            return;
+        }
+    };
+
+    let active_tasks: Vec<_> = tasks.iter().filter(|t| !t.is_deleted()).collect();
+    if active_tasks.is_empty() {
+        println!("No tasks as of {}", as_of);
+        press_enter();
+        return;
+    }
+
+    println!("List of tasks as of {}:", as_of);
+    println!();
+    for task in active_tasks {
+        println!("  {} - {}", task.id, task.title);
+        if let Some(description) = &task.description {
+            println!("    Description: {}", description);
+        }
+        if let Some(due_date) = &task.due_date {
+            println!("    Due date: {}", due_date);
+        }
+        println!("    Status: {}", task.status);
+        println!();
+    }
+    press_enter();
+}
+
+fn run_tui(list_of_tasks: &mut TaskList) {
+    if let Err(e) = tui::run(list_of_tasks) {
+        println!("Error running the full-screen interface: {}", e);
+        press_enter();
+    }
+}
+
+fn run_board(list_of_tasks: &mut TaskList) {
+    if let Err(e) = board::run(list_of_tasks) {
+        println!("Error showing the board: {}", e);
+        press_enter();
+    }
+}
+
+fn show_calendar(list_of_tasks: &TaskList) {
+    let month_input = get_user_input("Enter month to show (mm.YYYY, leave empty for the current month)");
+    let today = NaiveDateTimeWrapper::now().0.date();
+
+    let (month, year) = if month_input.is_empty() {
+        (today.month(), today.year())
+    } else {
+        let parsed_month = month_input
+            .split_once('.')
+            .and_then(|(month, year)| Some((month.parse::<u32>().ok()?, year.parse::<i32>().ok()?)))
+            .filter(|(month, _)| (1..=12).contains(month));
+
+        match parsed_month {
+            Some(parsed_month) => parsed_month,
+            None => {
+                println!("Invalid month: {}", month_input);
+                press_enter();
+                return;
+            }
+        }
+    };
+
+    println!("{}", CLEAR_SCREEN);
+    let tasks: Vec<&Task> = list_of_tasks.active_tasks().collect();
+    if let Err(e) = calendar::print_month(&tasks, year, month) {
+        println!("{}", e);
+    }
+    println!();
+    press_enter();
+}
+
+fn show_agenda(list_of_tasks: &TaskList) {
+    let days_input = get_user_input(&format!(
+        "Enter number of days to show, up to {} (leave empty for 7)",
+        MAX_AGENDA_DAYS
+    ));
+    let days = if days_input.is_empty() {
+        7
+    } else {
+        match days_input.parse::<u32>() {
+            Ok(days) if days <= MAX_AGENDA_DAYS => days,
+            Ok(_) => {
+                println!("The agenda shows at most {} days", MAX_AGENDA_DAYS);
+                press_enter();
+                return;
+            }
+            Err(_) => {
+                println!("Invalid number of days: {}", days_input);
+                press_enter();
+                return;
+            }
+        }
+    };
+
+    println!("{}", CLEAR_SCREEN);
+    let tasks: Vec<&Task> = list_of_tasks.active_tasks().collect();
+    calendar::print_agenda(&tasks, days);
+    press_enter();
+}
+
+fn help() {
+    println!("{}", CLEAR_SCREEN);
+    println!("To-Do app by @realsnack");
+    println!("https://github.com/Realsnack/rust-to-do-app");
+    println!();
+    println!();
+    println!("Press tab at a prompt to complete commands, task Ids, fields and statuses.");
+    println!("Use the arrow keys to go through previous commands and Ctrl-R to search them.");
+    println!("Ctrl-C or Ctrl-D at the command prompt exits like exit, at other prompts they cancel the input.");
+    println!();
+    println!("Commands can also be given on one line together with their arguments, for example:");
+    println!("  add \"Fix login\" due:fri +auth");
+    println!("  update 5 status:completed desc:\"New description\" -auth");
+    println!("  delete 3,5,8-12");
+    println!("  update status:completed tag:old set due:+2d -old");
+    println!("Fields are title:, desc:, due:, status: and priority:, +tag adds a tag and -tag removes it.");
+    println!("Due dates can be dd.mm.YYYY, \"dd.mm.YYYY HH:MM\", today, tomorrow or a weekday like fri, also at the prompts and in tui.");
+    println!("Statuses can be written as \"in progress\", in-progress or inprogress.");
+    println!("Tasks can be chosen by Ids, ranges like 8-12 or filters like status:completed tag:old.");
+    println!("Changes to more than one task are shown before they are applied. With filters, update needs set before the changes.");
+    println!("A missing title, task Id or change is asked for like when the command is given on its own.");
+    println!("Add with arguments doesn't ask for a description or due date, give them with desc: and due: instead.");
+    println!();
+    println!("You can use these commands:");
+    println!("  {} - Allows you to add a new task", "add".bold().green());
+    println!("    The application will first ask you to enter a title for the task");
+    println!("    Then it will ask you to enter a description for the task - if you don't want to enter a description, just press enter");
+    println!("    Finally it will ask you to enter a due date for the task - if you don't want to enter a due date, just press enter");
+    println!("    The task is automatically created with status 'Not started'");
+    println!("  {} - list all tasks", "list".bold().cyan());
+    println!("    This will print a list of all tasks");
+    println!("    By default tasks are shown in a table fitted to the width of the terminal. The columns can be chosen with the list columns setting or for one run with --columns id,title,due");
+    println!("    Only tasks matching a query are listed when one is given, e.g. list status:inprogress and (tag:api or priority>=high) and due<+7d");
+    println!("    Queries compare id, title, desc, status, priority, tag and due with :, =, !=, <, <=, > and >=, and combine them with and, or, not and parentheses");
+    println!("    Due dates can be compared to days like today or fri, to times from now like +7d, or to none. Saved views are included with @name");
+    println!("    With the detailed list layout each task will have an ID, title, description, and due date. If a task doesn't have a description or due date, it will be marked as such");
+    println!("  {} - searches titles and descriptions of all tasks", "search".bold().cyan());
+    println!("    Finds tasks containing all of the given words or \"phrases in quotes\", ignoring case. A /regular expression/ between slashes can be used as well");
+    println!("    Tasks matching in the title are listed first, tasks in trash and in the archive are included and marked as such");
+    println!(
+        "  {} - allows you to update a task",
+        "update".bold().yellow()
+    );
+    println!("    This will allow you to update a task");
+    println!("    You will first be asked to enter the ID of the task you want to update. Then you will be asked to select which field you want to update. Finally you will be asked to enter the new value for the field");
+    println!("  {} - moves a task to trash", "delete".bold().red());
+    println!("    This will allow you to delete a task");
+    println!("    You will be asked to enter the ID of the task you want to delete. The task is moved to trash and hidden from the list");
+    println!("  {} - lists tasks in trash", "trash".bold().red());
+    println!("  {} - restores a task from trash", "restore".bold().green());
+    println!("    You will be asked to enter the ID of the task you want to restore");
+    println!("  {} - permanently deletes tasks in trash", "empty-trash".bold().red());
+    println!("    Tasks in trash can also be purged automatically after a number of days, see {}", "config".bold().grey());
+    println!("  {} - archives completed tasks", "archive".bold().cyan());
+    println!("    You will be asked to enter the ID of a completed task to archive, or leave it empty to archive all completed tasks");
+    println!("    Archived tasks are kept in a separate file and hidden from the list. Completed tasks can also be archived automatically after a number of days, see {}", "config".bold().grey());
+    println!("  {} - searches archived tasks", "archived".bold().cyan());
+    println!("    You will be asked for text to search for in titles and descriptions, leave it empty to list the whole archive");
+    println!("  {} - moves a task back from the archive", "unarchive".bold().cyan());
+    println!("  {} - undoes the last change", "undo".bold().blue());
+    println!("    Adding, updating and deleting tasks can be undone step by step, even after restarting the program");
+    println!("  {} - redoes the last undone change", "redo".bold().blue());
+    println!("  {} - shows all changes made to a task", "history".bold().blue());
+    println!("    Every change is recorded in an event log when the program exits. You will be asked to enter the ID of the task to show the changes for");
+    println!("  {} - lists tasks as they were at a past date", "as-of".bold().blue());
+    println!("    You will be asked to enter a date and time, the list is then rebuilt from the event log");
+    println!("  {} - opens the full-screen interface", "tui".bold().magenta());
+    println!("    Shows a scrollable table of tasks with details of the selected one. Keyboard shortcuts are listed at the bottom of the screen, press q to return here");
+    println!("  {} - shows tasks on a board by status", "board".bold().magenta());
+    println!("    Tasks are shown in one column per status. Select a task with the arrow keys and press < or > to move it to the previous or next status");
+    println!("  {} - shows a month calendar of due dates", "calendar".bold().magenta());
+    println!("    You will be asked for the month to show (mm.YYYY), leave it empty for the current month. Days with tasks due are highlighted, overdue tasks are listed in red at the top");
+    println!("  {} - lists tasks due in the next days", "agenda".bold().magenta());
+    println!("    You will be asked for the number of days to show, leave it empty for a week. Tasks are listed under their due date, overdue tasks in red at the top");
+    println!("  {} - lists, saves and deletes views", "view".bold().cyan());
+    println!("    A view is a saved query for list. Save one with view save this-week due<+7d status!=completed, then use it with list @this-week");
+    println!("    view on its own lists the saved views and view delete this-week removes one");
+    println!("  {} - shows statistics and a burndown chart", "stats".bold().blue());
+    println!("    Shows tasks per status, overdue tasks, tasks completed per day and week, the average time from creation to completion and how many tasks were open on each of the last days");
+    println!("    Archived tasks are included. Give a query to only count some tasks, e.g. stats tag:api for a project tagged +api");
+    println!("  {} - exports tasks to other applications", "export".bold().green());
+    println!("    export ics tasks.ics writes an iCalendar file with a to-do for every task that calendar clients can import or subscribe to");
+    println!("    Exporting again updates the same to-dos in the client. Tasks in trash and in the archive are left out");
+    println!("    export todo.txt writes one line per task in the todo.txt format, with tags as +projects or @contexts. Descriptions are left out");
+    println!("    export markdown tasks.md writes a checklist grouped by status, or by project (the first tag of each task) with --by-project");
+    println!("    export taskwarrior tasks.json writes JSON like task export of Taskwarrior, which task import reads. The first tag is the project");
+    println!("    export html report.html writes a report page grouped by status with overdue tasks highlighted. It needs no other files and can be sorted and filtered in the browser");
+    println!("  {} - imports tasks from other applications", "import".bold().green());
+    println!("    import ics invites.ics adds a task for every to-do in an iCalendar file. With --events, events are added as tasks due at their start as well");
+    println!("    Importing a file again updates the tasks added before instead of adding them twice. Import can be undone with undo");
+    println!("    import todo.txt todo.txt adds a task for every line of a todo.txt file. Priorities (A) to (C), x for done, +projects, @contexts and due: are kept");
+    println!("    import markdown tasks.md reads back an exported checklist: checked boxes complete tasks, edited lines update them and new lines add tasks");
+    println!("    New items then get an id comment in the file so the next import matches them, nothing else in it changes. Removing a line doesn't delete its task");
+    println!("    import taskwarrior tasks.json reads the output of task export. Annotations become the description, the project the first tag; deleted tasks are left out");
+    println!("  {} - upgrades stored files to the current format", "migrate".bold().grey());
+    println!("    tasks.csv, archive.csv and config.bin written by older versions are upgraded automatically when the program starts, after a copy of each is made");
+    println!("    Start the program with migrate --dry-run to see what would change without upgrading anything");
+    println!("  {} - moves tasks to another storage backend", "backend".bold().grey());
+    println!("    backend postgres copies all tasks, the archive, the task counter and the event log to Postgres, reads them back to check nothing was lost and then uses Postgres");
+    println!("    The copy is refused if the target already holds tasks. The old backend is backed up and then emptied, so tasks can be moved back to it later");
+    println!("    Postgres is connected to with the connection string in {}, e.g. postgres://todo@localhost/todo", storage::DATABASE_URL_VARIABLE);
+    println!("  {} - lists and restores backups of tasks", "backup".bold().grey());
+    println!(
+        "    tasks.csv and archive.csv, or the tasks in Postgres written like them, are copied to the backups directory before every save that changes them. The newest {} are kept unless backup retention is changed in config",
+        backup::DEFAULT_BACKUP_RETENTION
+    );
+    println!("    backup list shows the backups, backup restore 20261019-143005 changes all tasks back to how they were in that backup. Restoring can be undone with undo");
+    println!("  {} - changes settings", "config".bold().grey());
+    println!("    Available settings: trash retention, auto archive, list layout, list columns, wrap list values, backup retention");
+    println!("  {} - prints this help message", "help".bold().grey());
+    println!("  {} - exits the program", "exit".bold().magenta());
+    println!();
+    press_enter();
+
+    println!("{}", CLEAR_SCREEN);
+}
+
+// Returns whether the tasks were written to disk
+fn save_tasks(list_of_tasks: &mut TaskList, backup_retention: usize) -> bool {
+    let mut overwrite_external_changes = false;
+
+    loop {
+        match list_of_tasks.save_tasks(overwrite_external_changes, backup_retention) {
+            Ok(_) => {
+                println!("Tasks saved");
+                return true;
+            }
+            Err(SaveError::ExternallyModified) => {
+                println!("Tasks were saved by another instance since they were loaded");
+                let choice = get_user_input(
+                    "Choose 'merge' to reload and merge the changes, 'overwrite' to replace them with yours or 'discard' to drop your changes",
+                )
+                .to_lowercase();
+                println!();
+
+                match choice.as_str() {
+                    "merge" => match list_of_tasks.merge_external_changes() {
+                        Ok(report) => {
+                            for (task_id, resolution) in report.conflicts {
+                                match resolution {
+                                    Resolution::KeptOurs => {
+                                        println!("Task {} was changed in both instances, kept your version", task_id)
+                                    }
+                                    Resolution::KeptTheirsDeletedByUs => println!(
+                                        "Task {} was deleted here but changed by the other instance, kept its version",
+                                        task_id
+                                    ),
+                                    Resolution::KeptOursDeletedByThem => println!(
+                                        "Task {} was deleted by the other instance but changed here, kept your version",
+                                        task_id
+                                    ),
+                                }
+                            }
+                            for (old_id, new_id) in report.renumbered {
+                                println!("Task {} was added in both instances, yours is now task {}", old_id, new_id);
+                            }
+                        }
+                        Err(e) => {
+                            println!("Error merging tasks: {}", e);
+                            return false;
+                        }
+                    },
+                    "overwrite" => overwrite_external_changes = true,
+                    "discard" => {
+                        println!("Your changes were discarded");
+                        return false;
+                    }
+                    _ => println!("Invalid choice: {}", choice),
+                }
+            }
+            Err(e) => {
+                println!("Error saving tasks: {}", e);
+                return false;
+            }
+        }
+    }
 }
 
 fn press_enter() {
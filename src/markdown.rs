@@ -0,0 +1,351 @@
+use std::str::FromStr;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Timelike};
+
+use crate::command_line::parse_priority;
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+const DOCUMENT_TITLE: &str = "# Tasks";
+const NO_PROJECT: &str = "No project";
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATE_TIME_FORMAT: &str = "%Y-%m-%dT%H:%M";
+// Tasks due on a day without a time are due at the end of it
+const END_OF_DAY: (u32, u32) = (23, 59);
+const IN_PROGRESS: &str = "status:in-progress";
+// Description lines are indented by this much below their task
+const INDENT: &str = "  ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Grouping {
+    #[default]
+    Status,
+    // By the first tag of each task, tags are used as projects
+    Project,
+}
+
+// Writes a checklist like `- [ ] Fix login +auth due:2026-10-30 <!-- id:3 list:0a1b2c3d -->`
+// under a heading per status or project. The id comments let an edited file be
+// imported again.
+pub fn to_markdown(tasks: &[&Task], grouping: Grouping, list_id: u32) -> String {
+    let mut lines = vec![DOCUMENT_TITLE.to_string()];
+
+    let groups: Vec<(String, Vec<&Task>)> = match grouping {
+        Grouping::Status => TaskStatus::all()
+            .into_iter()
+            .map(|status| {
+                let group = tasks.iter().filter(|t| t.status == status).copied().collect();
+                (status.to_string(), group)
+            })
+            .collect(),
+        Grouping::Project => {
+            let mut projects: Vec<&str> = tasks.iter().filter_map(|t| t.tags.first()).map(|t| t.as_str()).collect();
+            projects.sort_by_key(|project| project.to_lowercase());
+            projects.dedup();
+
+            let mut groups: Vec<(String, Vec<&Task>)> = projects
+                .into_iter()
+                .map(|project| {
+                    let group = tasks.iter().filter(|t| t.tags.first().is_some_and(|tag| tag == project)).copied().collect();
+                    (project.to_string(), group)
+                })
+                .collect();
+            groups.push((NO_PROJECT.to_string(), tasks.iter().filter(|t| t.tags.is_empty()).copied().collect()));
+            groups
+        }
+    };
+
+    for (heading, group) in groups.iter().filter(|(_, group)| !group.is_empty()) {
+        lines.push(String::new());
+        lines.push(format!("## {}", heading));
+        lines.push(String::new());
+
+        for task in group {
+            lines.push(task_line(task, grouping, list_id));
+            if let Some(description) = &task.description {
+                lines.extend(description.lines().map(|line| format!("{}{}", INDENT, line)));
+            }
+        }
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn task_line(task: &Task, grouping: Grouping, list_id: u32) -> String {
+    let checkbox = if task.status == TaskStatus::Completed { "[x]" } else { "[ ]" };
+    let mut words = vec!["-".to_string(), checkbox.to_string(), task.title.clone()];
+
+    // The project is the heading the task is under
+    let skipped_tags = if grouping == Grouping::Project { 1 } else { 0 };
+    words.extend(task.tags.iter().skip(skipped_tags).map(|tag| format!("+{}", tag)));
+    if let Some(due_date) = task.due_date {
+        let (hour, minute) = END_OF_DAY;
+        let format = if due_date.0.hour() == hour && due_date.0.minute() == minute { DATE_FORMAT } else { DATE_TIME_FORMAT };
+        words.push(format!("due:{}", due_date.0.format(format)));
+    }
+    if let Some(priority) = task.priority {
+        words.push(format!("priority:{}", priority.to_string().to_lowercase()));
+    }
+    // Grouped by status, the heading shows the task is in progress
+    if grouping == Grouping::Project && task.status == TaskStatus::InProgress {
+        words.push(IN_PROGRESS.to_string());
+    }
+    words.push(id_comment(task.id, list_id));
+
+    words.join(" ")
+}
+
+pub fn id_comment(task_id: usize, list_id: u32) -> String {
+    format!("<!-- id:{} list:{:08x} -->", task_id, list_id)
+}
+
+// Reads the checklist items of a Markdown file as tasks. Items with an id
+// comment of this list keep their id, new items and items of other lists have
+// id 0. Checked items are completed, unchecked ones get the status of their
+// heading or are not started.
+pub fn from_markdown(contents: &str, list_id: u32) -> Result<Vec<Task>, String> {
+    let mut tasks: Vec<Task> = Vec::new();
+    let mut heading_status = None;
+    let mut heading_project = None;
+    // Whether the lines after the last item belong to it as its description
+    let mut in_item = false;
+
+    for (index, line) in contents.lines().enumerate() {
+        if let Some(heading) = line.strip_prefix("## ") {
+            let heading = heading.trim();
+            heading_status = TaskStatus::from_str(heading).ok();
+            heading_project = match heading_status {
+                None if heading != NO_PROJECT => Some(heading.to_string()),
+                _ => None,
+            };
+            in_item = false;
+            continue;
+        }
+
+        if let Some((checked, text)) = parse_item(line) {
+            let task = parse_task(text, checked, &heading_status, &heading_project, list_id)
+                .map_err(|e| format!("Line {}: {}", index + 1, e))?;
+            tasks.push(task);
+            in_item = true;
+            continue;
+        }
+
+        match (line.strip_prefix(INDENT), tasks.last_mut()) {
+            (Some(description_line), Some(task)) if in_item => {
+                let description = match task.description.take() {
+                    Some(description) => format!("{}\n{}", description, description_line.trim()),
+                    None => description_line.trim().to_string(),
+                };
+                task.set_description(description);
+            }
+            _ => in_item = false,
+        }
+    }
+
+    Ok(tasks)
+}
+
+// Gives each checklist item the id comment of the task it was imported to,
+// task_ids are in the order from_markdown read the items. All other lines,
+// and items that already have the right comment, are kept as they are.
+pub fn set_id_comments(contents: &str, task_ids: &[usize], list_id: u32) -> String {
+    let mut task_ids = task_ids.iter();
+
+    contents
+        .split_inclusive('\n')
+        .map(|line| {
+            let text = line.trim_end_matches(['\r', '\n']);
+            if parse_item(text).is_none() {
+                return line.to_string();
+            }
+            let comment = match task_ids.next() {
+                Some(task_id) => id_comment(*task_id, list_id),
+                None => return line.to_string(),
+            };
+            if text.ends_with(&comment) {
+                return line.to_string();
+            }
+
+            let item = text.rsplit_once("<!-- id:").map_or(text, |(item, _)| item);
+            format!("{} {}{}", item.trim_end(), comment, &line[text.len()..])
+        })
+        .collect()
+}
+
+// `- [ ] text` or `* [x] text`, returns whether the box is checked and the text
+fn parse_item(line: &str) -> Option<(bool, &str)> {
+    let rest = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "))?;
+    let checked = match rest.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+
+    Some((checked, rest[3..].trim()))
+}
+
+fn parse_task(
+    text: &str,
+    checked: bool,
+    heading_status: &Option<TaskStatus>,
+    heading_project: &Option<String>,
+    list_id: u32,
+) -> Result<Task, String> {
+    let (text, id) = match text.rsplit_once("<!-- id:") {
+        Some((text, comment)) => {
+            let comment = comment.trim_end().trim_end_matches("-->");
+            let (id, comment_list_id) = comment.trim().split_once(" list:").unwrap_or((comment.trim(), ""));
+            let id = id.parse::<usize>().map_err(|_| format!("Invalid task id: {}", id))?;
+            (text, if comment_list_id.trim() == format!("{:08x}", list_id) { id } else { 0 })
+        }
+        None => (text, 0),
+    };
+
+    let mut title_words = Vec::new();
+    let mut tags: Vec<String> = heading_project.iter().cloned().collect();
+    let mut due_date = None;
+    let mut priority = None;
+    let mut in_progress = false;
+
+    for word in text.split_whitespace() {
+        if let Some(tag) = word.strip_prefix('+').filter(|tag| !tag.is_empty()) {
+            tags.push(tag.to_string());
+            continue;
+        }
+        match word.split_once(':') {
+            Some(("due", value)) => due_date = Some(parse_due_date(value)?),
+            Some(("priority", value)) => priority = parse_priority(value)?,
+            _ if word == IN_PROGRESS => in_progress = true,
+            _ => title_words.push(word),
+        }
+    }
+
+    if title_words.is_empty() {
+        return Err("Task has no title".to_string());
+    }
+
+    let mut task = Task::new(id, title_words.join(" "));
+    task.due_date = due_date;
+    task.set_tags(tags);
+    task.set_priority(priority);
+    task.set_status(match heading_status {
+        _ if checked => TaskStatus::Completed,
+        _ if in_progress => TaskStatus::InProgress,
+        Some(TaskStatus::InProgress) => TaskStatus::InProgress,
+        _ => TaskStatus::NotStarted,
+    });
+
+    Ok(task)
+}
+
+fn parse_due_date(value: &str) -> Result<NaiveDateTimeWrapper, String> {
+    if let Ok(due_date) = NaiveDateTime::parse_from_str(value, DATE_TIME_FORMAT) {
+        return Ok(NaiveDateTimeWrapper::new(due_date));
+    }
+
+    let (hour, minute) = END_OF_DAY;
+    NaiveDate::parse_from_str(value, DATE_FORMAT)
+        .ok()
+        .zip(NaiveTime::from_hms_opt(hour, minute, 0))
+        .map(|(date, time)| NaiveDateTimeWrapper::new(date.and_time(time)))
+        .ok_or_else(|| format!("Invalid due date: {} (use YYYY-MM-DD or YYYY-MM-DDTHH:MM)", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::priority::Priority;
+
+    const LIST_ID: u32 = 0x0a1b2c3d;
+
+    fn tasks() -> Vec<Task> {
+        let mut login = Task::new(3, "Fix login".to_string());
+        login.set_tags(vec!["api".to_string(), "auth".to_string()]);
+        login.set_due_date(NaiveDateTimeWrapper::parse_from_str("2026-10-30 23:59:00", "%Y-%m-%d %H:%M:%S").unwrap());
+        login.set_status(TaskStatus::InProgress);
+        login.set_description("Users get logged out\nafter a minute".to_string());
+        let mut release = Task::new(5, "Release".to_string());
+        release.set_due_date(NaiveDateTimeWrapper::parse_from_str("2026-11-02 14:30:00", "%Y-%m-%d %H:%M:%S").unwrap());
+        release.set_priority(Some(Priority::High));
+        release.set_status(TaskStatus::Completed);
+        let notes = Task::new(6, "Write notes".to_string());
+
+        vec![login, release, notes]
+    }
+
+    fn assert_same_tasks(imported: &[Task], exported: &[Task]) {
+        assert_eq!(imported.len(), exported.len());
+        for (task, original) in imported.iter().zip(exported) {
+            assert_eq!(task.id, original.id);
+            assert_eq!(task.title, original.title);
+            assert_eq!(task.description, original.description);
+            assert_eq!(task.due_date, original.due_date);
+            assert_eq!(task.status, original.status);
+            assert_eq!(task.priority, original.priority);
+            assert_eq!(task.tags, original.tags);
+        }
+    }
+
+    #[test]
+    fn imports_exported_checklists() {
+        let tasks = tasks();
+        let task_refs: Vec<&Task> = tasks.iter().collect();
+
+        let by_status = to_markdown(&task_refs, Grouping::Status, LIST_ID);
+        assert!(by_status.contains("## In Progress\n\n- [ ] Fix login +api +auth due:2026-10-30 <!-- id:3 list:0a1b2c3d -->\n  Users get logged out\n"));
+        let mut imported = from_markdown(&by_status, LIST_ID).unwrap();
+        imported.sort_by_key(|task| task.id);
+        assert_same_tasks(&imported, &tasks);
+
+        let by_project = to_markdown(&task_refs, Grouping::Project, LIST_ID);
+        assert!(by_project.contains("## api\n\n- [ ] Fix login +auth due:2026-10-30 status:in-progress"));
+        let mut imported = from_markdown(&by_project, LIST_ID).unwrap();
+        imported.sort_by_key(|task| task.id);
+        assert_same_tasks(&imported, &tasks);
+    }
+
+    #[test]
+    fn items_of_other_lists_and_new_items_get_no_id() {
+        let contents = "## Not Started\n\
+            - [ ] From another list <!-- id:3 list:ffffffff -->\n\
+            * [X] Added by hand\n\
+            - [ ] Ours <!-- id:4 list:0a1b2c3d -->\n";
+
+        let tasks = from_markdown(contents, LIST_ID).unwrap();
+
+        assert_eq!(tasks.iter().map(|t| t.id).collect::<Vec<usize>>(), vec![0, 0, 4]);
+        assert_eq!(tasks[1].status, TaskStatus::Completed);
+        assert!(from_markdown("- [ ] due:2026-10-30", LIST_ID).is_err());
+        assert!(from_markdown("- [ ] Pay rent due:soon", LIST_ID).is_err());
+    }
+
+    #[test]
+    fn adds_id_comments_without_changing_other_lines() {
+        let contents = "# My tasks\r\n\
+            \r\n\
+            Some notes\r\n\
+            - [ ] New item +home\r\n\
+            \x20 with a description\r\n\
+            - [x] Known item <!-- id:4 list:0a1b2c3d -->\r\n\
+            - [ ] Copied item <!-- id:9 list:ffffffff -->\r\n\
+            - not an item\r\n";
+
+        let updated = set_id_comments(contents, &[12, 4, 13], LIST_ID);
+
+        assert_eq!(
+            updated,
+            "# My tasks\r\n\
+            \r\n\
+            Some notes\r\n\
+            - [ ] New item +home <!-- id:12 list:0a1b2c3d -->\r\n\
+            \x20 with a description\r\n\
+            - [x] Known item <!-- id:4 list:0a1b2c3d -->\r\n\
+            - [ ] Copied item <!-- id:13 list:0a1b2c3d -->\r\n\
+            - not an item\r\n"
+        );
+        // Importing it again matches the items to the same tasks
+        let ids: Vec<usize> = from_markdown(&updated, LIST_ID).unwrap().iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![12, 4, 13]);
+    }
+}
@@ -0,0 +1,187 @@
+use crate::task::Task;
+
+// How a task changed on both sides was merged
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    // Both changed it, our version was kept
+    KeptOurs,
+    // We deleted it and they changed it, their version was kept
+    KeptTheirsDeletedByUs,
+    // They deleted it and we changed it, our version was kept
+    KeptOursDeletedByThem,
+}
+
+pub struct MergeReport {
+    // Tasks changed on both sides and which version was kept, by id
+    pub conflicts: Vec<(usize, Resolution)>,
+    // Tasks we added whose id was taken by another instance, as (old, new) id
+    pub renumbered: Vec<(usize, usize)>,
+}
+
+// Three-way merge of task lists by id.
+// base - tasks as they were when we loaded them
+// ours - tasks as they are in this instance now
+// theirs - tasks as they are on disk now
+pub fn merge_tasks(base: &[Task], ours: &[Task], theirs: &[Task]) -> (Vec<Task>, MergeReport) {
+    let find = |tasks: &[Task], id: usize| tasks.iter().find(|t| t.id == id).cloned();
+
+    let mut merged: Vec<Task> = Vec::new();
+    let mut colliding: Vec<Task> = Vec::new();
+    let mut report = MergeReport {
+        conflicts: Vec::new(),
+        renumbered: Vec::new(),
+    };
+
+    for their_task in theirs {
+        let base_task = find(base, their_task.id);
+        let our_task = find(ours, their_task.id);
+
+        match (base_task, our_task) {
+            (Some(base_task), Some(our_task)) => {
+                if our_task == base_task {
+                    merged.push(their_task.clone());
+                } else {
+                    if *their_task != base_task && *their_task != our_task {
+                        report.conflicts.push((their_task.id, Resolution::KeptOurs));
+                    }
+                    merged.push(our_task);
+                }
+            }
+            (Some(base_task), None) => {
+                // We deleted it - keep it only if they changed it in the meantime
+                if *their_task != base_task {
+                    report.conflicts.push((their_task.id, Resolution::KeptTheirsDeletedByUs));
+                    merged.push(their_task.clone());
+                }
+            }
+            (None, Some(our_task)) => {
+                // Both sides added a task under the same id
+                merged.push(their_task.clone());
+                if our_task != *their_task {
+                    colliding.push(our_task);
+                }
+            }
+            (None, None) => merged.push(their_task.clone()),
+        }
+    }
+
+    for our_task in ours {
+        if theirs.iter().any(|t| t.id == our_task.id) {
+            continue;
+        }
+
+        match find(base, our_task.id) {
+            // They deleted it - keep it only if we changed it in the meantime
+            Some(base_task) => {
+                if *our_task != base_task {
+                    report.conflicts.push((our_task.id, Resolution::KeptOursDeletedByThem));
+                    merged.push(our_task.clone());
+                }
+            }
+            None => merged.push(our_task.clone()),
+        }
+    }
+
+    let mut next_id = merged
+        .iter()
+        .chain(colliding.iter())
+        .map(|t| t.id)
+        .max()
+        .unwrap_or(0);
+
+    for mut task in colliding {
+        next_id += 1;
+        report.renumbered.push((task.id, next_id));
+        task.id = next_id;
+        merged.push(task);
+    }
+
+    (merged, report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(id: usize, title: &str) -> Task {
+        Task::new(id, title.to_string())
+    }
+
+    fn titles(tasks: &[Task]) -> Vec<(usize, &str)> {
+        let mut titles: Vec<(usize, &str)> = tasks.iter().map(|t| (t.id, t.title.as_str())).collect();
+        titles.sort();
+        titles
+    }
+
+    #[test]
+    fn takes_changes_made_on_either_side() {
+        let base = vec![task(1, "One"), task(2, "Two"), task(3, "Three")];
+        let ours = vec![task(1, "One changed by us"), task(2, "Two"), task(3, "Three")];
+        let theirs = vec![task(1, "One"), task(2, "Two changed by them"), task(3, "Three")];
+
+        let (merged, report) = merge_tasks(&base, &ours, &theirs);
+
+        assert_eq!(
+            titles(&merged),
+            vec![(1, "One changed by us"), (2, "Two changed by them"), (3, "Three")]
+        );
+        assert!(report.conflicts.is_empty());
+        assert!(report.renumbered.is_empty());
+    }
+
+    #[test]
+    fn keeps_our_version_when_both_changed_a_task() {
+        let base = vec![task(1, "One")];
+        let ours = vec![task(1, "Ours")];
+        let theirs = vec![task(1, "Theirs")];
+
+        let (merged, report) = merge_tasks(&base, &ours, &theirs);
+
+        assert_eq!(titles(&merged), vec![(1, "Ours")]);
+        assert_eq!(report.conflicts, vec![(1, Resolution::KeptOurs)]);
+    }
+
+    #[test]
+    fn same_change_on_both_sides_is_no_conflict() {
+        let base = vec![task(1, "One")];
+        let ours = vec![task(1, "Same")];
+        let theirs = vec![task(1, "Same")];
+
+        let (merged, report) = merge_tasks(&base, &ours, &theirs);
+
+        assert_eq!(titles(&merged), vec![(1, "Same")]);
+        assert!(report.conflicts.is_empty());
+    }
+
+    #[test]
+    fn deletions_lose_against_changes() {
+        let base = vec![task(1, "One"), task(2, "Two"), task(3, "Three"), task(4, "Four")];
+        // We deleted 1 and 2 and changed 3, they deleted 3 and 4 and changed 1
+        let ours = vec![task(3, "Three changed by us"), task(4, "Four")];
+        let theirs = vec![task(1, "One changed by them"), task(2, "Two")];
+
+        let (merged, report) = merge_tasks(&base, &ours, &theirs);
+
+        assert_eq!(titles(&merged), vec![(1, "One changed by them"), (3, "Three changed by us")]);
+        assert_eq!(
+            report.conflicts,
+            vec![(1, Resolution::KeptTheirsDeletedByUs), (3, Resolution::KeptOursDeletedByThem)]
+        );
+    }
+
+    #[test]
+    fn renumbers_our_tasks_added_under_a_taken_id() {
+        let base = vec![task(1, "One")];
+        let ours = vec![task(1, "One"), task(2, "Added by us"), task(3, "Also added by us")];
+        let theirs = vec![task(1, "One"), task(2, "Added by them")];
+
+        let (merged, report) = merge_tasks(&base, &ours, &theirs);
+
+        assert_eq!(
+            titles(&merged),
+            vec![(1, "One"), (2, "Added by them"), (3, "Also added by us"), (4, "Added by us")]
+        );
+        assert_eq!(report.renumbered, vec![(2, 4)]);
+        assert!(report.conflicts.is_empty());
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+use bincode::Options;
+
+use crate::backup::DEFAULT_BACKUP_RETENTION;
+use crate::configuration::{self, new_list_id, CONFIG_FILE, CONFIG_VERSION};
+use crate::persistence::{self, CsvTable, ARCHIVE_FILE, COLUMNS, SCHEMA_VERSION, TASKS_FILE};
+use crate::supported_persistence::SupportedPersistence;
+use crate::table::{Column, ListLayout};
+
+// A step that upgrades stored data from one format version to the next. To
+// change a format, bump its version and add a migration to the list for it.
+pub struct Migration<T> {
+    // Version of the data after this migration, it applies to the version before
+    pub version: u32,
+    pub description: &'static str,
+    // Changes the data and describes what was changed
+    pub apply: fn(&mut T) -> Result<Vec<String>, String>,
+}
+
+pub struct MigrationReport {
+    pub path: String,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub changes: Vec<String>,
+    // Where the file was copied before it was migrated
+    pub backup_path: Option<String>,
+}
+
+fn task_migrations() -> Vec<Migration<CsvTable>> {
+    vec![Migration {
+        version: 2,
+        description: "record the schema version and name all columns",
+        apply: add_missing_columns,
+    }]
+}
+
+// Migrations get the fields encoded after the version
+fn config_migrations() -> Vec<Migration<Vec<u8>>> {
+    vec![
+        Migration {
+            version: 2,
+            description: "record the format version and fill in settings added since",
+            apply: fill_in_unversioned_settings,
+        },
+        Migration {
+            version: 3,
+            description: "add the number of backups to keep",
+            apply: add_backup_retention,
+        },
+    ]
+}
+
+// Upgrades tasks.csv, archive.csv and config.bin to the current versions, each
+// after copying it to a backup. With dry_run nothing is written. Snapshots of
+// the event log are not migrated, they are read in any version.
+pub fn migrate_files(dry_run: bool) -> Result<Vec<MigrationReport>, Box<dyn Error>> {
+    let mut reports = Vec::new();
+
+    for path in [TASKS_FILE, ARCHIVE_FILE] {
+        if !Path::new(path).exists() {
+            continue;
+        }
+
+        let mut table = persistence::read_csv_table(path)?;
+        let from_version = table.schema_version;
+        let changes = run_migrations(path, &mut table, from_version, SCHEMA_VERSION, &task_migrations())?;
+        if changes.is_empty() {
+            continue;
+        }
+
+        table.schema_version = SCHEMA_VERSION;
+        let backup_path = if dry_run {
+            None
+        } else {
+            let backup_path = back_up(path, from_version)?;
+            persistence::write_csv_table(path, &table)?;
+            Some(backup_path)
+        };
+        reports.push(MigrationReport {
+            path: path.to_string(),
+            from_version,
+            to_version: SCHEMA_VERSION,
+            changes,
+            backup_path,
+        });
+    }
+
+    if Path::new(CONFIG_FILE).exists() {
+        let encoded_config = fs::read(CONFIG_FILE)?;
+        let (from_version, payload) = configuration::split_version(&encoded_config);
+        let mut payload = payload.to_vec();
+        let changes = run_migrations(CONFIG_FILE, &mut payload, from_version, CONFIG_VERSION, &config_migrations())?;
+
+        if !changes.is_empty() {
+            let backup_path = if dry_run {
+                None
+            } else {
+                let backup_path = back_up(CONFIG_FILE, from_version)?;
+                fs::write(CONFIG_FILE, configuration::join_version(CONFIG_VERSION, &payload))?;
+                Some(backup_path)
+            };
+            reports.push(MigrationReport {
+                path: CONFIG_FILE.to_string(),
+                from_version,
+                to_version: CONFIG_VERSION,
+                changes,
+                backup_path,
+            });
+        }
+    }
+
+    Ok(reports)
+}
+
+// Applies the migrations from from_version up to current_version in order,
+// returns the changes made or nothing when the data is current
+fn run_migrations<T>(
+    path: &str,
+    data: &mut T,
+    from_version: u32,
+    current_version: u32,
+    migrations: &[Migration<T>],
+) -> Result<Vec<String>, String> {
+    if from_version > current_version {
+        return Err(format!(
+            "{} has version {}, this version of the program only reads up to version {}",
+            path, from_version, current_version
+        ));
+    }
+
+    let mut changes = Vec::new();
+    let mut version = from_version;
+    while version < current_version {
+        let migration = migrations
+            .iter()
+            .find(|migration| migration.version == version + 1)
+            .ok_or_else(|| format!("No migration of {} from version {}", path, version))?;
+
+        changes.push(format!("Version {} to {}: {}", version, migration.version, migration.description));
+        let details = (migration.apply)(data).map_err(|e| format!("Migrating {} failed: {}", path, e))?;
+        changes.extend(details.into_iter().map(|detail| format!("  {}", detail)));
+        version = migration.version;
+    }
+
+    Ok(changes)
+}
+
+fn back_up(path: &str, version: u32) -> Result<String, Box<dyn Error>> {
+    let backup_path = format!("{}.v{}.bak", path, version);
+    fs::copy(path, &backup_path)?;
+    Ok(backup_path)
+}
+
+// Version 1 files were read by position, so headers can be missing or named
+// differently and rows can be shorter than the header
+fn add_missing_columns(table: &mut CsvTable) -> Result<Vec<String>, String> {
+    let mut changes = Vec::new();
+
+    for (index, header) in table.headers.iter_mut().enumerate() {
+        let normalized = header.trim().to_lowercase();
+        let name = match COLUMNS.get(index) {
+            // Unnamed columns get the name of the column at their position
+            Some(column) if normalized.is_empty() => column.to_string(),
+            _ => normalized,
+        };
+        if *header != name {
+            changes.push(format!("rename column {} from '{}' to '{}'", index + 1, header, name));
+            *header = name;
+        }
+    }
+
+    for column in COLUMNS {
+        if !table.headers.iter().any(|header| header == column) {
+            changes.push(format!("add empty column '{}'", column));
+            table.headers.push(column.to_string());
+        }
+    }
+
+    let column_count = table.headers.len();
+    let short_rows: Vec<String> = table
+        .rows
+        .iter()
+        .filter(|(_, row)| row.len() < column_count)
+        .map(|(line, _)| line.to_string())
+        .collect();
+    if !short_rows.is_empty() {
+        changes.push(format!("fill in missing values of the rows on lines {}", short_rows.join(", ")));
+    }
+    for (_, row) in &mut table.rows {
+        row.resize(column_count.max(row.len()), String::new());
+    }
+
+    Ok(changes)
+}
+
+// Before the version was recorded config.bin only held the persistence and the
+// task counter. The settings added since get their defaults and the list a new id.
+fn fill_in_unversioned_settings(payload: &mut Vec<u8>) -> Result<Vec<String>, String> {
+    let (persistence, task_counter): (SupportedPersistence, usize) = bincode::options()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(payload)
+        .map_err(|e| format!("it holds more or less than the persistence and the task counter: {}", e))?;
+
+    let config = (
+        persistence,
+        task_counter,
+        None::<u32>,
+        None::<u32>,
+        ListLayout::Table,
+        Column::default_columns(),
+        false,
+        BTreeMap::<String, String>::new(),
+        new_list_id(),
+    );
+    *payload = bincode::serialize(&config).map_err(|e| e.to_string())?;
+
+    Ok(vec!["add default settings for trash retention, auto archive, list layout, list columns, wrapping of list values and views".to_string()])
+}
+
+// Fields are encoded one after another, so a new last field is appended
+fn add_backup_retention(payload: &mut Vec<u8>) -> Result<Vec<String>, String> {
+    let encoded_retention = bincode::serialize(&DEFAULT_BACKUP_RETENTION).map_err(|e| e.to_string())?;
+    payload.extend(encoded_retention);
+
+    Ok(vec![format!("keep {} backups", DEFAULT_BACKUP_RETENTION)])
+}
+
+#[cfg(test)]
+mod tests {
+    use bincode::Options;
+
+    use super::*;
+    use crate::configuration::Configuration;
+
+    // Decoded like bincode::deserialize, but all bytes have to be used
+    fn migrate_config(payload: &[u8]) -> Result<Configuration, String> {
+        let mut payload = payload.to_vec();
+        run_migrations(CONFIG_FILE, &mut payload, 1, CONFIG_VERSION, &config_migrations())?;
+
+        bincode::options()
+            .with_fixint_encoding()
+            .reject_trailing_bytes()
+            .deserialize(&payload)
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn migrates_config_written_before_any_settings_were_added() {
+        // Only the persistence and the task counter
+        let payload = bincode::serialize(&(SupportedPersistence::CSV, 12usize)).unwrap();
+
+        let config = migrate_config(&payload).unwrap();
+
+        assert!(matches!(config.persistence, SupportedPersistence::CSV));
+        assert_eq!(config.task_counter, 12);
+        assert_eq!(config.trash_retention_days, None);
+        assert_eq!(config.auto_archive_days, None);
+        assert_eq!(config.list_columns, Column::default_columns());
+        assert!(config.views.is_empty());
+        assert_eq!(config.list_layout, ListLayout::Table);
+        assert!(!config.wrap_list_values);
+        assert_eq!(config.backup_retention, DEFAULT_BACKUP_RETENTION);
+    }
+
+    #[test]
+    fn adds_backup_retention_to_version_2_config() {
+        let mut views = BTreeMap::new();
+        views.insert("work".to_string(), "tag:work".to_string());
+        let payload = bincode::serialize(&(
+            SupportedPersistence::CSV,
+            3usize,
+            Some(30u32),
+            Some(7u32),
+            ListLayout::Detailed,
+            vec![Column::Id, Column::Title],
+            true,
+            views.clone(),
+            0x1234u32,
+        ))
+        .unwrap();
+
+        let mut migrated = payload.clone();
+        run_migrations(CONFIG_FILE, &mut migrated, 2, CONFIG_VERSION, &config_migrations()).unwrap();
+        let config: Configuration = bincode::deserialize(&migrated).unwrap();
+
+        assert_eq!(config.task_counter, 3);
+        assert_eq!(config.trash_retention_days, Some(30));
+        assert_eq!(config.auto_archive_days, Some(7));
+        assert_eq!(config.list_layout, ListLayout::Detailed);
+        assert_eq!(config.list_columns, vec![Column::Id, Column::Title]);
+        assert!(config.wrap_list_values);
+        assert_eq!(config.views, views);
+        assert_eq!(config.list_id, 0x1234);
+        assert_eq!(config.backup_retention, DEFAULT_BACKUP_RETENTION);
+    }
+
+    #[test]
+    fn refuses_to_migrate_config_that_cant_be_read() {
+        let mut payload = bincode::serialize(&(SupportedPersistence::CSV, 3usize)).unwrap();
+        payload.push(7);
+
+        assert!(migrate_config(&payload).is_err());
+        assert!(migrate_config(b"not a config").is_err());
+    }
+}
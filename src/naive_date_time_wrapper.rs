@@ -1,9 +1,9 @@
 use std::str::FromStr;
 
-use chrono::NaiveDateTime;
-use serde::{Serialize, Serializer};
+use chrono::{DateTime, Local, NaiveDateTime, ParseError, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct NaiveDateTimeWrapper(pub NaiveDateTime);
 
 impl NaiveDateTimeWrapper {
@@ -11,14 +11,31 @@ impl NaiveDateTimeWrapper {
         NaiveDateTimeWrapper(date_time)
     }
 
-    pub fn parse_from_str(date_time: &str, fmt: &str) -> Result<NaiveDateTimeWrapper, ()> {
-        let date_time = NaiveDateTime::parse_from_str(date_time, fmt);
+    // Truncated to whole seconds, which is the precision we store
+    pub fn now() -> NaiveDateTimeWrapper {
+        let now = chrono::Local::now().naive_local();
+        NaiveDateTimeWrapper(now.with_nanosecond(0).unwrap_or(now))
+    }
 
-        match date_time {
-            Ok(date_time) => Ok(NaiveDateTimeWrapper(date_time)),
-            Err(_) => Err(()),
+    // Our times are local, other applications often use UTC
+    pub fn from_utc(date_time: NaiveDateTime) -> NaiveDateTimeWrapper {
+        let local: DateTime<Local> = Utc.from_utc_datetime(&date_time).into();
+        NaiveDateTimeWrapper(local.naive_local())
+    }
+
+    // Times skipped by a daylight saving change are left as they are
+    pub fn to_utc(&self) -> NaiveDateTime {
+        match Local.from_local_datetime(&self.0).earliest() {
+            Some(local) => local.with_timezone(&Utc).naive_utc(),
+            None => self.0,
         }
     }
+
+    pub fn parse_from_str(date_time: &str, fmt: &str) -> Result<NaiveDateTimeWrapper, ParseError> {
+        let date_time = NaiveDateTime::parse_from_str(date_time, fmt)?;
+
+        Ok(NaiveDateTimeWrapper(date_time))
+    }
 }
 
 impl std::fmt::Display for NaiveDateTimeWrapper {
@@ -34,6 +51,15 @@ impl Serialize for NaiveDateTimeWrapper {
     }
 }
 
+impl<'de> Deserialize<'de> for NaiveDateTimeWrapper {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where D: Deserializer<'de> {
+        let date_time = String::deserialize(deserializer)?;
+        NaiveDateTimeWrapper::from_str(&date_time)
+            .map_err(|_| serde::de::Error::custom(format!("invalid date: {}", date_time)))
+    }
+}
+
 impl FromStr for NaiveDateTimeWrapper {
     type Err = ();
 
@@ -1,11 +1,86 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
 use std::str::FromStr;
 
+use csv::StringRecord;
+
 use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
-use crate::{task::Task, task_status::TaskStatus};
+use crate::priority::Priority;
+use crate::task::{parse_tags, Task};
+use crate::task_status::TaskStatus;
+
+pub const TASKS_FILE: &str = "tasks.csv";
+pub const TASKS_LOCK_FILE: &str = "tasks.csv.lock";
+pub const ARCHIVE_FILE: &str = "archive.csv";
+
+// Version of the file format, written on the first line as `# schema_version: 2`
+pub const SCHEMA_VERSION: u32 = 2;
+const SCHEMA_VERSION_PREFIX: &str = "# schema_version:";
+// Columns in the order they are written
+pub const COLUMNS: [&str; 11] = [
+    "id",
+    "title",
+    "description",
+    "due_date",
+    "status",
+    "deleted_at",
+    "completed_at",
+    "tags",
+    "priority",
+    "created_at",
+    "uid",
+];
+const REQUIRED_COLUMNS: [&str; 2] = ["id", "title"];
+
+pub struct LoadedTasks {
+    pub tasks: Vec<Task>,
+    // Rows that were skipped and other problems found in the file
+    pub errors: Vec<RowError>,
+}
+
+#[derive(Debug)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+impl std::fmt::Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
 
-// Save task to csv using the csv crate
-pub fn save_tasks_to_csv(tasks: &Vec<Task>) -> Result<(), Box<dyn std::error::Error>> {
-    let mut writer = csv::Writer::from_path("tasks.csv")?;
+#[derive(Debug)]
+pub enum SaveError {
+    // Another instance saved since we last loaded or saved
+    ExternallyModified,
+    Io(Box<dyn std::error::Error>),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SaveError::ExternallyModified => {
+                write!(f, "tasks were saved by another instance")
+            }
+            SaveError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+// Save task to csv using the csv crate, after a line with the schema version
+pub fn save_tasks_to_csv(path: &str, tasks: &[Task]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = File::create(path)?;
+    writeln!(file, "{} {}", SCHEMA_VERSION_PREFIX, SCHEMA_VERSION)?;
+
+    let mut writer = csv::Writer::from_writer(file);
+    // The header is only written with the first task otherwise
+    if tasks.is_empty() {
+        writer.write_record(COLUMNS)?;
+    }
 
     for task in tasks {
         writer.serialize(task)?;
@@ -15,35 +90,299 @@ pub fn save_tasks_to_csv(tasks: &Vec<Task>) -> Result<(), Box<dyn std::error::Er
     Ok(())
 }
 
-pub fn load_tasks_from_csv() -> Result<Vec<Task>, Box<dyn std::error::Error>> {
+// Columns are found by their header, so they can be in any order and all but
+// id and title can be missing. Rows that can't be read are skipped and returned
+// as errors, a file that can't be read at all is an error.
+pub fn load_tasks_from_csv(path: &str) -> Result<LoadedTasks, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(path)?;
+
+    let schema_version = schema_version(&contents)?;
+    if schema_version > SCHEMA_VERSION {
+        return Err(format!(
+            "{} has schema version {}, this version of the program only reads up to version {}",
+            path, schema_version, SCHEMA_VERSION
+        )
+        .into());
+    }
+
+    let csv_contents = without_version_line(&contents);
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(csv_contents.as_bytes());
+
+    // Files saved without any tasks used to have no header
+    if reader.headers()?.is_empty() {
+        return Ok(LoadedTasks { tasks: Vec::new(), errors: Vec::new() });
+    }
+
+    let mut columns = HashMap::new();
+    let mut errors = Vec::new();
+    // Blank lines before the header, like the emptied version line, are skipped
+    let header_line = csv_contents.lines().position(|line| !line.trim().is_empty()).unwrap_or_default() as u64 + 1;
+    for (index, header) in reader.headers()?.iter().enumerate() {
+        let header = header.trim().to_lowercase();
+        if !COLUMNS.contains(&header.as_str()) {
+            errors.push(RowError {
+                line: header_line,
+                message: format!("unknown column '{}' is ignored and won't be saved", header),
+            });
+        }
+        columns.entry(header).or_insert(index);
+    }
+    for required in REQUIRED_COLUMNS {
+        if !columns.contains_key(required) {
+            return Err(format!("{} has no {} column", path, required).into());
+        }
+    }
+
     let mut tasks: Vec<Task> = Vec::new();
+    for result in reader.records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map(|position| position.line()).unwrap_or_default();
+                errors.push(RowError { line, message: e.to_string() });
+                continue;
+            }
+        };
+        let line = record.position().map(|position| position.line()).unwrap_or_default();
+
+        match parse_task(&record, &columns) {
+            Ok(task) if tasks.iter().any(|t| t.id == task.id) => errors.push(RowError {
+                line,
+                message: format!("task Id {} is used by an earlier row", task.id),
+            }),
+            Ok(task) => tasks.push(task),
+            Err(message) => errors.push(RowError { line, message }),
+        }
+    }
 
-    let mut reader = csv::Reader::from_path("tasks.csv")?;
+    Ok(LoadedTasks { tasks, errors })
+}
+
+// The raw contents of a tasks file, used to migrate it between schema versions
+pub struct CsvTable {
+    pub schema_version: u32,
+    pub headers: Vec<String>,
+    // Values of each row along with the line it starts on
+    pub rows: Vec<(u64, Vec<String>)>,
+}
 
-    // Read lines, split by comma, and create a task
+pub fn read_csv_table(path: &str) -> Result<CsvTable, Box<dyn std::error::Error>> {
+    let contents = fs::read_to_string(path)?;
+    let csv_contents = without_version_line(&contents);
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(csv_contents.as_bytes());
+
+    let headers = reader.headers()?.iter().map(|header| header.to_string()).collect();
+    let mut rows = Vec::new();
     for result in reader.records() {
         let record = result?;
-        let mut task = Task::new(
-            record.get(0).unwrap().parse()?,
-            record.get(1).unwrap().parse()?,
-        );
-        let description:String = record.get(2).unwrap().parse()?;
-        let due_date = NaiveDateTimeWrapper::from_str(record.get(3).unwrap());
-        let status = TaskStatus::from_str(record.get(4).unwrap());
+        let line = record.position().map(|position| position.line()).unwrap_or_default();
+        rows.push((line, record.iter().map(|value| value.to_string()).collect()));
+    }
+
+    Ok(CsvTable {
+        schema_version: schema_version(&contents)?,
+        headers,
+        rows,
+    })
+}
+
+pub fn write_csv_table(path: &str, table: &CsvTable) -> Result<(), Box<dyn std::error::Error>> {
+    let mut file = File::create(path)?;
+    writeln!(file, "{} {}", SCHEMA_VERSION_PREFIX, table.schema_version)?;
+
+    let mut writer = csv::WriterBuilder::new().flexible(true).from_writer(file);
+    writer.write_record(&table.headers)?;
+    for (_, row) in &table.rows {
+        writer.write_record(row)?;
+    }
 
-        task.set_description(description);
+    writer.flush()?;
+    Ok(())
+}
+
+// Files written before the schema version was recorded are version 1
+fn schema_version(contents: &str) -> Result<u32, String> {
+    let version = match contents.lines().next().and_then(|line| line.strip_prefix(SCHEMA_VERSION_PREFIX)) {
+        Some(version) => version.trim(),
+        None => return Ok(1),
+    };
+
+    version.parse().map_err(|_| format!("Invalid schema version: {}", version))
+}
+
+// The version line is emptied rather than removed, so rows keep their line
+// numbers. Rows aren't read with # as a comment, as titles can start with it.
+fn without_version_line(contents: &str) -> String {
+    if !contents.starts_with(SCHEMA_VERSION_PREFIX) {
+        return contents.to_string();
+    }
+
+    match contents.split_once('\n') {
+        Some((_, rest)) => format!("\n{}", rest),
+        None => String::new(),
+    }
+}
+
+fn parse_task(record: &StringRecord, columns: &HashMap<String, usize>) -> Result<Task, String> {
+    // Empty values are the same as missing ones
+    let value = |column: &str| {
+        columns
+            .get(column)
+            .and_then(|index| record.get(*index))
+            .filter(|value| !value.trim().is_empty())
+    };
+    let date = |column: &str| {
+        value(column)
+            .map(|date| NaiveDateTimeWrapper::from_str(date.trim()).map_err(|_| format!("invalid {}: {}", column, date)))
+            .transpose()
+    };
 
-        if let Ok(due_date) = due_date {
-            task.set_due_date(due_date);
+    let id = value("id").ok_or("missing id")?;
+    let id = id.trim().parse().map_err(|_| format!("invalid id: {}", id))?;
+    let title = value("title").ok_or("missing title")?;
+
+    let mut task = Task::new(id, title.to_string());
+    if let Some(description) = value("description") {
+        task.set_description(description.to_string());
+    }
+    task.due_date = date("due_date")?;
+    if let Some(status) = value("status") {
+        let status = TaskStatus::from_str(status).map_err(|_| format!("invalid status: {}", status))?;
+        task.set_status(status);
+    }
+    task.set_deleted_at(date("deleted_at")?);
+    task.set_completed_at(date("completed_at")?);
+    task.set_tags(value("tags").map(parse_tags).unwrap_or_default());
+    if let Some(priority) = value("priority") {
+        let priority = Priority::from_str(priority).map_err(|_| format!("invalid priority: {}", priority))?;
+        task.set_priority(Some(priority));
+    }
+    task.set_created_at(date("created_at")?);
+    task.set_uid(value("uid").map(|uid| uid.to_string()));
+
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    // A file of its own in the temp directory for each test, removed when dropped
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &str) -> TempFile {
+            let path = std::env::temp_dir().join(format!("persistence-{}-{}.csv", std::process::id(), name));
+            fs::write(&path, contents).unwrap();
+            TempFile(path)
         }
 
-        match status {
-            Ok(status) => task.set_status(status),
-            Err(_) => task.set_status(TaskStatus::NotStarted),
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
         }
 
-        tasks.push(task);
+        fn load(&self) -> LoadedTasks {
+            load_tasks_from_csv(self.path()).unwrap()
+        }
     }
 
-    Ok(tasks)
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn loads_saved_tasks() {
+        let mut task = Task::new(3, "Fix login, \"soon\"".to_string());
+        task.set_description("First line\nsecond line".to_string());
+        task.set_due_date(NaiveDateTimeWrapper::from_str("2026-10-30 23:59:00").unwrap());
+        task.set_status(TaskStatus::Completed);
+        task.set_completed_at(Some(NaiveDateTimeWrapper::from_str("2026-10-20 10:00:00").unwrap()));
+        task.set_tags(vec!["api".to_string(), "auth".to_string()]);
+        task.set_priority(Some(Priority::High));
+        task.set_created_at(Some(NaiveDateTimeWrapper::from_str("2026-10-01 09:00:00").unwrap()));
+        task.set_uid(Some("abc@example.com".to_string()));
+        let tasks = vec![task, Task::new(4, "# Not a comment".to_string())];
+        let file = TempFile::new("saved", "");
+
+        save_tasks_to_csv(file.path(), &tasks).unwrap();
+        let loaded = file.load();
+
+        assert_eq!(loaded.tasks, tasks);
+        assert!(loaded.errors.is_empty());
+        assert!(fs::read_to_string(file.path()).unwrap().starts_with("# schema_version: 2\n"));
+
+        save_tasks_to_csv(file.path(), &[]).unwrap();
+        assert!(file.load().tasks.is_empty());
+    }
+
+    #[test]
+    fn finds_columns_by_their_header() {
+        let file = TempFile::new(
+            "headers",
+            "Title , ID,Status,Notes\n\
+             Write report,1,In Progress,x\n\
+             #hashtag title,2,,\n\
+             Short row,3\n",
+        );
+
+        let loaded = file.load();
+
+        let titles: Vec<(usize, &str)> = loaded.tasks.iter().map(|t| (t.id, t.title.as_str())).collect();
+        assert_eq!(titles, vec![(1, "Write report"), (2, "#hashtag title"), (3, "Short row")]);
+        assert_eq!(loaded.tasks[0].status, TaskStatus::InProgress);
+        assert_eq!(loaded.tasks[1].status, TaskStatus::NotStarted);
+        assert_eq!(loaded.tasks[1].description, None);
+        assert_eq!(loaded.errors.len(), 1);
+        assert_eq!(loaded.errors[0].to_string(), "line 1: unknown column 'notes' is ignored and won't be saved");
+
+        let versioned = TempFile::new("versioned-headers", "# schema_version: 2
+id,title,color
+1,One,red
+");
+        assert_eq!(versioned.load().errors[0].line, 2);
+    }
+
+    #[test]
+    fn skips_rows_that_cant_be_read() {
+        let file = TempFile::new(
+            "rows",
+            "# schema_version: 2\n\
+             id,title,due_date,priority\n\
+             1,One,,\n\
+             1,Same id,,\n\
+             x,Bad id,,\n\
+             2,,,\n\
+             3,Bad date,tomorrow,\n\
+             4,Bad priority,,urgent\n\
+             5,Five,,\n",
+        );
+
+        let loaded = file.load();
+
+        assert_eq!(loaded.tasks.iter().map(|t| t.id).collect::<Vec<usize>>(), vec![1, 5]);
+        let errors: Vec<String> = loaded.errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "line 4: task Id 1 is used by an earlier row",
+                "line 5: invalid id: x",
+                "line 6: missing title",
+                "line 7: invalid due_date: tomorrow",
+                "line 8: invalid priority: urgent",
+            ]
+        );
+    }
+
+    #[test]
+    fn refuses_files_it_cant_read() {
+        let no_title = TempFile::new("no-title", "id,description\n1,Something\n");
+        let newer = TempFile::new("newer", "# schema_version: 3\nid,title\n1,One\n");
+
+        assert!(load_tasks_from_csv(no_title.path()).is_err());
+        assert!(load_tasks_from_csv(newer.path()).is_err());
+    }
 }
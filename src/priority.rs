@@ -0,0 +1,59 @@
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+// Ordered from lowest to highest so priorities can be compared
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    Low,
+    Medium,
+    High,
+}
+
+impl Priority {
+    pub fn all() -> Vec<Priority> {
+        vec![Priority::Low, Priority::Medium, Priority::High]
+    }
+}
+
+impl Serialize for Priority {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for Priority {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let priority = String::deserialize(deserializer)?;
+        Priority::from_str(&priority)
+            .map_err(|_| serde::de::Error::custom(format!("invalid priority: {}", priority)))
+    }
+}
+
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Priority::Low => write!(f, "Low"),
+            Priority::Medium => write!(f, "Medium"),
+            Priority::High => write!(f, "High"),
+        }
+    }
+}
+
+impl FromStr for Priority {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Priority, Self::Err> {
+        match input.to_lowercase().trim() {
+            "low" => Ok(Priority::Low),
+            "medium" => Ok(Priority::Medium),
+            "high" => Ok(Priority::High),
+            _ => Err(()),
+        }
+    }
+}
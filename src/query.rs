@@ -0,0 +1,468 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+
+use crate::command_line;
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::priority::Priority;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+// Views can refer to other views, this stops a view from including itself
+const MAX_VIEW_DEPTH: usize = 10;
+
+// A parsed query like `status:inprogress and (tag:api or priority>=high) and due<+7d`.
+// Conditions next to each other without `and` or `or` must all match.
+#[derive(Debug)]
+pub enum Filter {
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+    Not(Box<Filter>),
+    Condition(Condition),
+}
+
+#[derive(Debug)]
+pub enum Condition {
+    Id(Operator, usize),
+    Title(Operator, String),
+    Description(Operator, String),
+    Status(Operator, TaskStatus),
+    Priority(Operator, Option<Priority>),
+    Tag(Operator, String),
+    Due(Operator, DueValue),
+}
+
+#[derive(Debug)]
+pub enum DueValue {
+    None,
+    // Days like today or 31.12.2026 are compared without the time
+    Day(NaiveDate),
+    // Relative times like +7d or now
+    Time(NaiveDateTime),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    // : and =
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Operator {
+    // Operators in the order they are tried, longer ones first
+    const SYMBOLS: [(&'static str, Operator); 7] = [
+        ("!=", Operator::NotEqual),
+        ("<=", Operator::LessOrEqual),
+        (">=", Operator::GreaterOrEqual),
+        (":", Operator::Equal),
+        ("=", Operator::Equal),
+        ("<", Operator::Less),
+        (">", Operator::Greater),
+    ];
+
+    fn compare<T: Ord>(&self, left: T, right: T) -> bool {
+        let ordering = left.cmp(&right);
+        match self {
+            Operator::Equal => ordering == Ordering::Equal,
+            Operator::NotEqual => ordering != Ordering::Equal,
+            Operator::Less => ordering == Ordering::Less,
+            Operator::LessOrEqual => ordering != Ordering::Greater,
+            Operator::Greater => ordering == Ordering::Greater,
+            Operator::GreaterOrEqual => ordering != Ordering::Less,
+        }
+    }
+
+    fn is_equality(&self) -> bool {
+        matches!(self, Operator::Equal | Operator::NotEqual)
+    }
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let symbol = Operator::SYMBOLS
+            .iter()
+            .find(|(_, operator)| operator == self)
+            .map(|(symbol, _)| *symbol)
+            .unwrap_or_default();
+        write!(f, "{}", symbol)
+    }
+}
+
+#[derive(Debug)]
+enum Token {
+    LeftParenthesis,
+    RightParenthesis,
+    And,
+    Or,
+    Not,
+    View(String),
+    Condition(String, Operator, String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::LeftParenthesis => write!(f, "("),
+            Token::RightParenthesis => write!(f, ")"),
+            Token::And => write!(f, "and"),
+            Token::Or => write!(f, "or"),
+            Token::Not => write!(f, "not"),
+            Token::View(name) => write!(f, "@{}", name),
+            Token::Condition(field, operator, value) => write!(f, "{}{}{}", field, operator, value),
+        }
+    }
+}
+
+impl Filter {
+    // Parses a query split into words, see command_line::split_words. Saved
+    // views are included with @name.
+    pub fn parse(words: &[String], views: &BTreeMap<String, String>) -> Result<Filter, String> {
+        Filter::parse_with_depth(words, views, 0)
+    }
+
+    fn parse_with_depth(
+        words: &[String],
+        views: &BTreeMap<String, String>,
+        depth: usize,
+    ) -> Result<Filter, String> {
+        if depth > MAX_VIEW_DEPTH {
+            return Err("Views refer to each other too deeply".to_string());
+        }
+
+        let tokens = tokenize(words)?;
+        if tokens.is_empty() {
+            return Err("Empty query".to_string());
+        }
+
+        let mut parser = Parser {
+            tokens,
+            position: 0,
+            views,
+            depth,
+        };
+        let filter = parser.parse_or()?;
+
+        match parser.tokens.get(parser.position) {
+            Some(token) => Err(format!("Unexpected '{}' in query", token)),
+            None => Ok(filter),
+        }
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            Filter::And(left, right) => left.matches(task) && right.matches(task),
+            Filter::Or(left, right) => left.matches(task) || right.matches(task),
+            Filter::Not(filter) => !filter.matches(task),
+            Filter::Condition(condition) => condition.matches(task),
+        }
+    }
+}
+
+impl Condition {
+    fn parse(field: &str, operator: Operator, value: &str) -> Result<Condition, String> {
+        let equality_only = |condition: Condition| {
+            if operator.is_equality() {
+                Ok(condition)
+            } else {
+                Err(format!("{} can only be compared with :, = or !=", field))
+            }
+        };
+
+        match field.to_lowercase().as_str() {
+            "id" => value
+                .parse()
+                .map(|id| Condition::Id(operator, id))
+                .map_err(|_| format!("Invalid task Id: {}", value)),
+            "title" => equality_only(Condition::Title(operator, value.to_lowercase())),
+            "description" | "desc" => equality_only(Condition::Description(operator, value.to_lowercase())),
+            "status" => equality_only(Condition::Status(operator, command_line::parse_status(value)?)),
+            "priority" => Ok(Condition::Priority(operator, command_line::parse_priority(value)?)),
+            "tag" => equality_only(Condition::Tag(operator, value.trim_start_matches('+').to_string())),
+            "due" => {
+                let due_value = parse_due_value(value)?;
+                match due_value {
+                    DueValue::None => equality_only(Condition::Due(operator, due_value)),
+                    _ => Ok(Condition::Due(operator, due_value)),
+                }
+            }
+            _ => Err(format!("Unknown field in query: {}", field)),
+        }
+    }
+
+    fn matches(&self, task: &Task) -> bool {
+        match self {
+            Condition::Id(operator, id) => operator.compare(task.id, *id),
+            Condition::Title(operator, text) => text_matches(*operator, &task.title, text),
+            Condition::Description(operator, text) => {
+                text_matches(*operator, task.description.as_deref().unwrap_or_default(), text)
+            }
+            Condition::Status(operator, status) => {
+                (task.status == *status) == (*operator == Operator::Equal)
+            }
+            // Tasks without a priority sort below low
+            Condition::Priority(operator, priority) => operator.compare(task.priority, *priority),
+            Condition::Tag(operator, tag) => task.has_tag(tag) == (*operator == Operator::Equal),
+            Condition::Due(operator, value) => match (task.due_date, value) {
+                (due_date, DueValue::None) => due_date.is_none() == (*operator == Operator::Equal),
+                (None, _) => *operator == Operator::NotEqual,
+                (Some(due_date), DueValue::Day(day)) => operator.compare(due_date.0.date(), *day),
+                (Some(due_date), DueValue::Time(time)) => operator.compare(due_date.0, *time),
+            },
+        }
+    }
+}
+
+// Text matches when it appears anywhere in the value, ignoring case
+fn text_matches(operator: Operator, value: &str, text: &str) -> bool {
+    let value = value.to_lowercase();
+    match operator {
+        Operator::Equal => value.contains(text),
+        Operator::NotEqual => !value.contains(text),
+        _ => false,
+    }
+}
+
+fn parse_due_value(value: &str) -> Result<DueValue, String> {
+    match value.to_lowercase().as_str() {
+        "none" => return Ok(DueValue::None),
+        "now" => return Ok(DueValue::Time(NaiveDateTimeWrapper::now().0)),
+        _ => {}
+    }
+
+    if value.starts_with(['+', '-']) {
+        let shift = command_line::parse_shift(value)?;
+        let time = command_line::shift_due_date(NaiveDateTimeWrapper::now(), shift)?;
+        return Ok(DueValue::Time(time.0));
+    }
+    // Only a date and time given in full is compared with the time
+    if let Ok(time) = NaiveDateTime::parse_from_str(value, "%d.%m.%Y %H:%M") {
+        return Ok(DueValue::Time(time));
+    }
+
+    command_line::parse_due_date(value).map(|due_date| DueValue::Day(due_date.0.date()))
+}
+
+fn tokenize(words: &[String]) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+
+    for word in words {
+        let mut word = word.as_str();
+        while let Some(rest) = word.strip_prefix('(') {
+            tokens.push(Token::LeftParenthesis);
+            word = rest;
+        }
+
+        let mut closing = 0;
+        while let Some(rest) = word.strip_suffix(')') {
+            closing += 1;
+            word = rest;
+        }
+
+        if !word.is_empty() {
+            tokens.push(word_token(word)?);
+        }
+        tokens.extend((0..closing).map(|_| Token::RightParenthesis));
+    }
+
+    Ok(tokens)
+}
+
+fn word_token(word: &str) -> Result<Token, String> {
+    match word.to_lowercase().as_str() {
+        "and" => return Ok(Token::And),
+        "or" => return Ok(Token::Or),
+        "not" => return Ok(Token::Not),
+        _ => {}
+    }
+
+    if let Some(name) = word.strip_prefix('@') {
+        return Ok(Token::View(name.to_string()));
+    }
+
+    // The field is the leading letters, the operator follows right after it
+    let field_end = word
+        .find(|c: char| !c.is_alphabetic())
+        .ok_or(format!("Expected a condition like status:completed, found '{}'", word))?;
+    let (field, rest) = word.split_at(field_end);
+
+    for (symbol, operator) in Operator::SYMBOLS {
+        if let Some(value) = rest.strip_prefix(symbol) {
+            if field.is_empty() || value.is_empty() {
+                break;
+            }
+            return Ok(Token::Condition(field.to_string(), operator, value.to_string()));
+        }
+    }
+
+    Err(format!("Expected a condition like status:completed, found '{}'", word))
+}
+
+// Recursive descent parser, `not` binds tightest, then `and`, then `or`
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    views: &'a BTreeMap<String, String>,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn parse_or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.parse_and()?;
+
+        while let Some(Token::Or) = self.tokens.get(self.position) {
+            self.position += 1;
+            filter = Filter::Or(Box::new(filter), Box::new(self.parse_and()?));
+        }
+
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.parse_unary()?;
+
+        loop {
+            match self.tokens.get(self.position) {
+                Some(Token::And) => self.position += 1,
+                Some(Token::Or) | Some(Token::RightParenthesis) | None => return Ok(filter),
+                // Conditions next to each other
+                Some(_) => {}
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.parse_unary()?));
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, String> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+
+        match token {
+            Some(Token::Not) => Ok(Filter::Not(Box::new(self.parse_unary()?))),
+            Some(Token::LeftParenthesis) => {
+                let filter = self.parse_or()?;
+                match self.tokens.get(self.position) {
+                    Some(Token::RightParenthesis) => {
+                        self.position += 1;
+                        Ok(filter)
+                    }
+                    _ => Err("Missing closing parenthesis in query".to_string()),
+                }
+            }
+            Some(Token::View(name)) => {
+                let query = self.views.get(name).ok_or(format!("Unknown view: @{}", name))?;
+                let words = command_line::split_words(query)?;
+                Filter::parse_with_depth(&words, self.views, self.depth + 1)
+            }
+            Some(Token::Condition(field, operator, value)) => {
+                Ok(Filter::Condition(Condition::parse(field, *operator, value)?))
+            }
+            Some(token) => Err(format!("Unexpected '{}' in query", token)),
+            None => Err("Query ends too early".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn tasks() -> Vec<Task> {
+        let now = NaiveDateTimeWrapper::now().0;
+
+        let mut api = Task::new(1, "Fix API login".to_string());
+        api.set_status(TaskStatus::InProgress);
+        api.set_priority(Some(Priority::High));
+        api.set_tags(vec!["api".to_string(), "auth".to_string()]);
+        api.set_due_date(NaiveDateTimeWrapper::new(now + Duration::days(3)));
+
+        let mut docs = Task::new(2, "Write docs".to_string());
+        docs.set_description("Explain the API".to_string());
+        docs.set_priority(Some(Priority::Low));
+        docs.set_due_date(NaiveDateTimeWrapper::new(now + Duration::days(30)));
+
+        let mut done = Task::new(3, "Release".to_string());
+        done.set_status(TaskStatus::Completed);
+        done.set_tags(vec!["api".to_string()]);
+
+        vec![api, docs, done]
+    }
+
+    fn matching(query: &str, views: &BTreeMap<String, String>) -> Result<Vec<usize>, String> {
+        let filter = Filter::parse(&command_line::split_words(query)?, views)?;
+        Ok(tasks().iter().filter(|task| filter.matches(task)).map(|task| task.id).collect())
+    }
+
+    fn ids(query: &str) -> Vec<usize> {
+        matching(query, &BTreeMap::new()).unwrap()
+    }
+
+    #[test]
+    fn matches_conditions_on_each_field() {
+        assert_eq!(ids("status:inprogress"), vec![1]);
+        assert_eq!(ids("status!=completed"), vec![1, 2]);
+        assert_eq!(ids("title:fix"), vec![1]);
+        assert_eq!(ids("desc:api"), vec![2]);
+        assert_eq!(ids("tag:+api"), vec![1, 3]);
+        assert_eq!(ids("priority>=low"), vec![1, 2]);
+        assert_eq!(ids("priority:none"), vec![3]);
+        assert_eq!(ids("id>1"), vec![2, 3]);
+        assert_eq!(ids("due:none"), vec![3]);
+    }
+
+    #[test]
+    fn compares_due_dates_with_relative_times() {
+        assert_eq!(ids("due<+7d"), vec![1]);
+        assert_eq!(ids("due>now"), vec![1, 2]);
+        assert_eq!(ids("due>=+2w"), vec![2]);
+        assert_eq!(ids("due<today"), Vec::<usize>::new());
+
+        let error = matching("due<+99999999999999w", &BTreeMap::new()).unwrap_err();
+        assert!(error.starts_with("Invalid due date shift"), "{}", error);
+    }
+
+    #[test]
+    fn combines_conditions() {
+        assert_eq!(ids("tag:api status:completed"), vec![3]);
+        assert_eq!(ids("status:completed or priority:low"), vec![2, 3]);
+        // and binds tighter than or
+        assert_eq!(ids("status:completed or priority:low and tag:api"), vec![3]);
+        assert_eq!(ids("(status:completed or priority:low) and not tag:api"), vec![2]);
+        assert_eq!(ids("not (tag:api)"), vec![2]);
+    }
+
+    #[test]
+    fn includes_saved_views() {
+        let mut views = BTreeMap::new();
+        views.insert("open".to_string(), "status!=completed".to_string());
+        views.insert("urgent".to_string(), "@open and priority:high".to_string());
+        views.insert("loop".to_string(), "@loop".to_string());
+
+        assert_eq!(matching("@urgent", &views), Ok(vec![1]));
+        assert_eq!(matching("@open tag:api", &views), Ok(vec![1]));
+        assert!(matching("@missing", &views).is_err());
+        assert!(matching("@loop", &views).is_err());
+    }
+
+    #[test]
+    fn reports_invalid_queries() {
+        for query in [
+            "",
+            "status:later",
+            "title<abc",
+            "color:red",
+            "(status:completed",
+            "status:completed)",
+            "status:completed and",
+            "fix",
+            "priority:urgent",
+        ] {
+            assert!(matching(query, &BTreeMap::new()).is_err(), "{}", query);
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use colorize::AnsiColor;
+use regex::{Regex, RegexBuilder};
+
+use crate::task::Task;
+
+// A match in the title counts this many times more than one in the description
+const TITLE_WEIGHT: usize = 3;
+
+// Every term has to match the title or description of a task. Terms are words,
+// phrases in quotes or a regular expression between slashes like /log(in|out)/.
+pub struct Query {
+    terms: Vec<Regex>,
+    // All terms in one expression, used to highlight matches
+    any_term: Regex,
+}
+
+pub struct SearchResult<'a> {
+    pub task: &'a Task,
+    pub score: usize,
+}
+
+impl Query {
+    pub fn parse(words: &[String]) -> Result<Query, String> {
+        let patterns: Vec<String> = words
+            .iter()
+            .filter(|word| !word.is_empty())
+            .map(|word| match word.strip_prefix('/').and_then(|w| w.strip_suffix('/')) {
+                Some(pattern) if !pattern.is_empty() => pattern.to_string(),
+                _ => regex::escape(word),
+            })
+            .collect();
+
+        if patterns.is_empty() {
+            return Err("Nothing to search for".to_string());
+        }
+
+        let terms = patterns
+            .iter()
+            .map(|pattern| build_regex(pattern))
+            .collect::<Result<Vec<Regex>, String>>()?;
+        let any_term = build_regex(&format!("({})", patterns.join(")|(")))?;
+
+        Ok(Query { terms, any_term })
+    }
+
+    // Score of task, or None if a term doesn't match
+    fn score(&self, task: &Task) -> Option<usize> {
+        let description = task.description.as_deref().unwrap_or_default();
+        let mut score = 0;
+
+        for term in &self.terms {
+            let title_matches = term.find_iter(&task.title).count();
+            let description_matches = term.find_iter(description).count();
+            if title_matches + description_matches == 0 {
+                return None;
+            }
+            score += title_matches * TITLE_WEIGHT + description_matches;
+        }
+
+        Some(score)
+    }
+
+    // Returns text with all matches of the query highlighted
+    pub fn highlight(&self, text: &str) -> String {
+        let mut highlighted = String::new();
+        let mut last_end = 0;
+
+        for found in self.any_term.find_iter(text).filter(|found| !found.is_empty()) {
+            highlighted.push_str(&text[last_end..found.start()]);
+            highlighted.push_str(&found.as_str().to_string().bold().yellow());
+            last_end = found.end();
+        }
+        highlighted.push_str(&text[last_end..]);
+
+        highlighted
+    }
+}
+
+fn build_regex(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| format!("Invalid regular expression: {}", e))
+}
+
+// Tasks matching query, best matches first
+pub fn search<'a>(tasks: impl Iterator<Item = &'a Task>, query: &Query) -> Vec<SearchResult<'a>> {
+    let mut results: Vec<SearchResult> = tasks
+        .filter_map(|task| query.score(task).map(|score| SearchResult { task, score }))
+        .collect();
+
+    results.sort_by(|a, b| b.score.cmp(&a.score).then(a.task.id.cmp(&b.task.id)));
+    results
+}
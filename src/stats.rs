@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use colorize::AnsiColor;
+
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+const COMPLETED_DAYS: i64 = 7;
+const COMPLETED_WEEKS: i64 = 4;
+const BURNDOWN_DAYS: i64 = 14;
+const BAR_WIDTH: usize = 40;
+
+// Prints counts, completion rates and a burndown of open tasks. Tasks without
+// created_at use the time from creation_times, taken from the event log.
+pub fn print_stats(tasks: &[&Task], creation_times: &HashMap<usize, NaiveDateTimeWrapper>) {
+    let created_at = |task: &Task| {
+        task.created_at
+            .or_else(|| creation_times.get(&task.id).copied())
+            .map(|created_at| created_at.0)
+    };
+    let today = NaiveDateTimeWrapper::now().0.date();
+
+    println!("{}", "Tasks by status".bold());
+    for status in TaskStatus::all() {
+        let count = tasks.iter().filter(|t| t.status == status).count();
+        println!("  {:<12} {}", status.to_string(), count);
+    }
+    let overdue = tasks.iter().filter(|t| t.is_overdue()).count();
+    println!("  {:<12} {}", "Overdue", if overdue > 0 { overdue.to_string().red() } else { overdue.to_string() });
+    println!();
+
+    let completed_on: Vec<NaiveDate> = tasks
+        .iter()
+        .filter_map(|t| t.completed_at)
+        .map(|completed_at| completed_at.0.date())
+        .collect();
+
+    println!("{}", "Completed per day".bold());
+    let daily: Vec<(String, usize)> = (0..COMPLETED_DAYS)
+        .rev()
+        .map(|days_ago| {
+            let day = today - Duration::days(days_ago);
+            let count = completed_on.iter().filter(|d| **d == day).count();
+            (day.format("%a %d.%m.").to_string(), count)
+        })
+        .collect();
+    print_bars(&daily);
+    println!();
+
+    println!("{}", "Completed per week".bold());
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday().into());
+    let weekly: Vec<(String, usize)> = (0..COMPLETED_WEEKS)
+        .rev()
+        .map(|weeks_ago| {
+            let start = week_start - Duration::weeks(weeks_ago);
+            let end = start + Duration::days(6);
+            let count = completed_on.iter().filter(|d| **d >= start && **d <= end).count();
+            (format!("Week of {}", start.format("%d.%m.")), count)
+        })
+        .collect();
+    print_bars(&weekly);
+    println!();
+
+    let completion_times: Vec<Duration> = tasks
+        .iter()
+        .filter_map(|t| Some(t.completed_at?.0 - created_at(t)?))
+        .collect();
+    print!("{} ", "Average time from creation to completion:".bold());
+    if completion_times.is_empty() {
+        println!("no completed tasks with a known creation time");
+    } else {
+        let total = completion_times.iter().fold(Duration::zero(), |total, time| total + *time);
+        println!("{}", format_duration(total / completion_times.len() as i32));
+    }
+    println!();
+
+    println!("{}", format!("Open tasks over the last {} days", BURNDOWN_DAYS).bold());
+    let burndown: Vec<(String, usize)> = (0..BURNDOWN_DAYS)
+        .rev()
+        .map(|days_ago| {
+            let day = today - Duration::days(days_ago);
+            let end_of_day = day.and_hms_opt(23, 59, 59).unwrap_or_default();
+            let open = tasks
+                .iter()
+                .filter(|t| is_open_at(t, created_at(t), end_of_day))
+                .count();
+            (day.format("%a %d.%m.").to_string(), open)
+        })
+        .collect();
+    print_bars(&burndown);
+}
+
+// A task is open from its creation until it was completed. Tasks with an unknown
+// creation time are counted as open from the start, completed tasks with an
+// unknown completion time as completed all along.
+fn is_open_at(task: &Task, created_at: Option<NaiveDateTime>, time: NaiveDateTime) -> bool {
+    let created = created_at.is_none_or(|created_at| created_at <= time);
+    let completed = match task.completed_at {
+        Some(completed_at) => completed_at.0 <= time,
+        None => task.status == TaskStatus::Completed,
+    };
+
+    created && !completed
+}
+
+fn print_bars(rows: &[(String, usize)]) {
+    let max = rows.iter().map(|(_, count)| *count).max().unwrap_or(0).max(1);
+    let label_width = rows.iter().map(|(label, _)| label.chars().count()).max().unwrap_or(0);
+
+    for (label, count) in rows {
+        let bar = "█".repeat(count * BAR_WIDTH / max);
+        println!("  {:<width$} {} {}", label, bar.cyan(), count, width = label_width);
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() - days * 24;
+    let minutes = duration.num_minutes() - duration.num_hours() * 60;
+
+    match (days, hours) {
+        (0, 0) => format!("{} minutes", minutes),
+        (0, _) => format!("{} hours {} minutes", hours, minutes),
+        _ => format!("{} days {} hours", days, hours),
+    }
+}
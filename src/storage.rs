@@ -0,0 +1,1019 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::str::FromStr;
+
+use chrono::NaiveDateTime;
+use postgres::types::ToSql;
+use postgres::{Client, NoTls, Row, Transaction};
+
+use crate::backup;
+use crate::event_log::{self, Event, EventKind, LoadedSnapshot, RebuiltTasks};
+use crate::file_lock::{FileFingerprint, FileLock};
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::persistence::{self, LoadedTasks, ARCHIVE_FILE, TASKS_FILE, TASKS_LOCK_FILE};
+use crate::priority::Priority;
+use crate::supported_persistence::SupportedPersistence;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+// Connection string of the Postgres database, e.g. postgres://todo@localhost/todo
+pub const DATABASE_URL_VARIABLE: &str = "DATABASE_URL";
+
+// Appended to the name of a file with rows that couldn't be read, for the copy kept of it
+const PROBLEM_BACKUP_SUFFIX: &str = ".bak";
+
+// Everything a backend keeps, copied as a whole when switching backends
+#[derive(Debug, Default)]
+pub struct StoredData {
+    pub tasks: Vec<Task>,
+    pub archived_tasks: Vec<Task>,
+    pub task_counter: usize,
+    // The event log, so task history survives the copy
+    pub events: Vec<Event>,
+}
+
+impl StoredData {
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty() && self.archived_tasks.is_empty() && self.events.is_empty()
+    }
+}
+
+// The tasks as loaded when the program starts
+pub struct LoadedList {
+    pub tasks: Vec<Task>,
+    pub archived_tasks: Vec<Task>,
+    pub logged_events: usize,
+    pub task_counter: usize,
+    // Problems found while loading, the tasks affected by them are left out
+    pub problems: Vec<String>,
+}
+
+// Changes whenever tasks are saved, used to tell whether another instance saved
+#[derive(Debug, Clone, PartialEq)]
+pub enum Revision {
+    File(Option<FileFingerprint>),
+    Database(i64),
+}
+
+// Keeps other instances from reading or writing tasks until dropped
+pub struct StorageLock {
+    _guard: Box<dyn Any>,
+}
+
+pub trait Storage {
+    fn lock(&self) -> Result<StorageLock, Box<dyn Error>>;
+    fn revision(&self) -> Result<Revision, Box<dyn Error>>;
+    fn load_list(&self) -> Result<LoadedList, Box<dyn Error>>;
+    // The tasks and archived tasks as they are stored now
+    fn load_tasks(&self) -> Result<(Vec<Task>, Vec<Task>), Box<dyn Error>>;
+    // Backs up the stored tasks before they are overwritten
+    fn back_up(&self, retention: usize) -> Result<(), Box<dyn Error>>;
+    // Starts the event log with a snapshot of the tasks, as older changes were
+    // never logged. Returns whether there was no log yet.
+    fn initialize_log(&self, tasks: &[Task], archived_tasks: &[Task]) -> Result<bool, Box<dyn Error>>;
+    // Writes the tasks and appends events to a log that holds logged_events
+    // events, returns the number of events logged now
+    fn write_tasks(
+        &self,
+        tasks: &[Task],
+        archived_tasks: &[Task],
+        events: &[Event],
+        logged_events: usize,
+        task_counter: usize,
+    ) -> Result<usize, Box<dyn Error>>;
+    fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>>;
+    // The latest snapshot taken after at most max_sequence events, and not after until if given
+    fn load_snapshot(
+        &self,
+        max_sequence: usize,
+        until: Option<NaiveDateTime>,
+    ) -> Result<Option<LoadedSnapshot>, Box<dyn Error>>;
+    // Everything stored, for copies to another backend
+    fn load(&self) -> Result<StoredData, Box<dyn Error>>;
+    // Replaces everything stored with data
+    fn save(&self, data: &StoredData) -> Result<(), Box<dyn Error>>;
+    // Removes everything stored, once it was moved to another backend
+    fn clear(&self) -> Result<(), Box<dyn Error>>;
+
+    fn event_count(&self) -> Result<usize, Box<dyn Error>> {
+        Ok(self.load_events()?.len())
+    }
+
+    // Tasks and archived tasks as they were at until, or as they are now
+    fn replay(&self, until: Option<NaiveDateTime>) -> Result<(Vec<Task>, Vec<Task>), Box<dyn Error>> {
+        let events = self.load_events()?;
+        let snapshot = self.load_snapshot(events.len(), until)?;
+        event_log::replay_events(&events, snapshot, until)
+    }
+
+    fn rebuild(&self) -> Result<RebuiltTasks, Box<dyn Error>> {
+        let events = self.load_events()?;
+        let snapshot = self.load_snapshot(events.len(), None)?;
+        let (tasks, archived_tasks) = event_log::replay_events(&events, snapshot, None)?;
+
+        Ok(RebuiltTasks {
+            tasks,
+            archived_tasks,
+            logged_events: events.len(),
+        })
+    }
+}
+
+pub fn storage_for(persistence: SupportedPersistence, task_counter: usize) -> Result<Box<dyn Storage>, Box<dyn Error>> {
+    match persistence {
+        SupportedPersistence::CSV => Ok(Box::new(CsvStorage {
+            task_counter: Cell::new(task_counter),
+        })),
+        SupportedPersistence::Postgres => Ok(Box::new(PostgresStorage::connect()?)),
+    }
+}
+
+// tasks.csv, archive.csv and events.log. The task counter is kept in the
+// configuration, so it is passed in, and a copied one is written there by the caller.
+pub struct CsvStorage {
+    task_counter: Cell<usize>,
+}
+
+impl Storage for CsvStorage {
+    fn lock(&self) -> Result<StorageLock, Box<dyn Error>> {
+        let lock = FileLock::acquire(TASKS_LOCK_FILE)?;
+        Ok(StorageLock { _guard: Box::new(lock) })
+    }
+
+    fn revision(&self) -> Result<Revision, Box<dyn Error>> {
+        Ok(Revision::File(FileFingerprint::of(TASKS_FILE)))
+    }
+
+    // Files with problems are copied before they get overwritten by the next save.
+    // The event log is the source of truth, the files are checked against it.
+    fn load_list(&self) -> Result<LoadedList, Box<dyn Error>> {
+        let mut problems = Vec::new();
+        let loaded_list = |tasks, archived_tasks, logged_events, problems| LoadedList {
+            tasks,
+            archived_tasks,
+            logged_events,
+            task_counter: self.task_counter.get(),
+            problems,
+        };
+
+        if !Path::new(TASKS_FILE).exists() {
+            if !event_log::exists() {
+                return Ok(loaded_list(Vec::new(), Vec::new(), 0, problems));
+            }
+            println!("{} not found, rebuilding tasks from {}", TASKS_FILE, event_log::EVENT_LOG_FILE);
+            let rebuilt = self.rebuild()?;
+            return Ok(loaded_list(rebuilt.tasks, rebuilt.archived_tasks, rebuilt.logged_events, problems));
+        }
+
+        let loaded_tasks = persistence::load_tasks_from_csv(TASKS_FILE)?;
+        let loaded_archive = load_archive()?;
+        for (path, errors) in [(TASKS_FILE, &loaded_tasks.errors), (ARCHIVE_FILE, &loaded_archive.errors)] {
+            if errors.is_empty() {
+                continue;
+            }
+            problems.extend(errors.iter().map(|error| format!("{} {}", path, error)));
+            let backup_path = format!("{}{}", path, PROBLEM_BACKUP_SUFFIX);
+            std::fs::copy(path, &backup_path)?;
+            problems.push(format!("{} was copied to {} before anything is left out", path, backup_path));
+        }
+
+        if self.initialize_log(&loaded_tasks.tasks, &loaded_archive.tasks)? {
+            return Ok(loaded_list(loaded_tasks.tasks, loaded_archive.tasks, 0, problems));
+        }
+
+        let rebuilt = self.rebuild()?;
+        let (tasks, archived_tasks, logged_events) = (rebuilt.tasks, rebuilt.archived_tasks, rebuilt.logged_events);
+
+        // Rows that couldn't be read are still in the log
+        if !problems.is_empty() {
+            problems.push(format!("Tasks were rebuilt from {}", event_log::EVENT_LOG_FILE));
+            return Ok(loaded_list(tasks, archived_tasks, logged_events, problems));
+        }
+
+        // The files were changed without going through the log, the changes are
+        // logged so the log matches them again. The files keep their order.
+        if !same_tasks(&loaded_tasks.tasks, &tasks) || !same_tasks(&loaded_archive.tasks, &archived_tasks) {
+            let events = event_log::events_between(
+                (&tasks, &archived_tasks),
+                (&loaded_tasks.tasks, &loaded_archive.tasks),
+                NaiveDateTimeWrapper::now(),
+            );
+            let logged_events =
+                event_log::append(&events, logged_events, &loaded_tasks.tasks, &loaded_archive.tasks)?;
+            problems.push(format!(
+                "{} or {} were changed outside of the app, {} changes were added to {}",
+                TASKS_FILE,
+                ARCHIVE_FILE,
+                events.len(),
+                event_log::EVENT_LOG_FILE
+            ));
+            return Ok(loaded_list(loaded_tasks.tasks, loaded_archive.tasks, logged_events, problems));
+        }
+
+        Ok(loaded_list(loaded_tasks.tasks, loaded_archive.tasks, logged_events, problems))
+    }
+
+    // Rows that can't be read were reported when loading and are left out
+    fn load_tasks(&self) -> Result<(Vec<Task>, Vec<Task>), Box<dyn Error>> {
+        let tasks = match Path::new(TASKS_FILE).exists() {
+            true => persistence::load_tasks_from_csv(TASKS_FILE)?.tasks,
+            false => Vec::new(),
+        };
+
+        Ok((tasks, load_archive()?.tasks))
+    }
+
+    fn back_up(&self, retention: usize) -> Result<(), Box<dyn Error>> {
+        backup::create_backup(retention)?;
+        Ok(())
+    }
+
+    fn initialize_log(&self, tasks: &[Task], archived_tasks: &[Task]) -> Result<bool, Box<dyn Error>> {
+        let created = !event_log::exists();
+        event_log::initialize(tasks, archived_tasks)?;
+        Ok(created)
+    }
+
+    fn write_tasks(
+        &self,
+        tasks: &[Task],
+        archived_tasks: &[Task],
+        events: &[Event],
+        logged_events: usize,
+        _task_counter: usize,
+    ) -> Result<usize, Box<dyn Error>> {
+        persistence::save_tasks_to_csv(TASKS_FILE, tasks)?;
+        // There is no archive until the first task gets archived
+        if !archived_tasks.is_empty() || Path::new(ARCHIVE_FILE).exists() {
+            persistence::save_tasks_to_csv(ARCHIVE_FILE, archived_tasks)?;
+        }
+
+        event_log::append(events, logged_events, tasks, archived_tasks)
+    }
+
+    fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>> {
+        event_log::load_events()
+    }
+
+    fn load_snapshot(
+        &self,
+        max_sequence: usize,
+        until: Option<NaiveDateTime>,
+    ) -> Result<Option<LoadedSnapshot>, Box<dyn Error>> {
+        event_log::load_snapshot(max_sequence, until)
+    }
+
+    fn load(&self) -> Result<StoredData, Box<dyn Error>> {
+        let mut data = StoredData {
+            task_counter: self.task_counter.get(),
+            events: event_log::load_events()?,
+            ..StoredData::default()
+        };
+
+        for (path, tasks) in [(TASKS_FILE, &mut data.tasks), (ARCHIVE_FILE, &mut data.archived_tasks)] {
+            if !Path::new(path).exists() {
+                continue;
+            }
+            let loaded_tasks = persistence::load_tasks_from_csv(path)?;
+            // Copying what could be read would silently lose the other rows
+            if let Some(error) = loaded_tasks.errors.first() {
+                return Err(format!("{} {}", path, error).into());
+            }
+            *tasks = loaded_tasks.tasks;
+        }
+
+        Ok(data)
+    }
+
+    fn save(&self, data: &StoredData) -> Result<(), Box<dyn Error>> {
+        persistence::save_tasks_to_csv(TASKS_FILE, &data.tasks)?;
+        persistence::save_tasks_to_csv(ARCHIVE_FILE, &data.archived_tasks)?;
+        self.task_counter.set(data.task_counter);
+        event_log::write_events(&data.events, &data.tasks, &data.archived_tasks)
+    }
+
+    fn clear(&self) -> Result<(), Box<dyn Error>> {
+        // tasks.csv last, without it the tasks would be rebuilt from the log
+        if Path::new(event_log::SNAPSHOT_DIRECTORY).exists() {
+            fs::remove_dir_all(event_log::SNAPSHOT_DIRECTORY)?;
+        }
+        for file in [event_log::EVENT_LOG_FILE, ARCHIVE_FILE, TASKS_FILE] {
+            if Path::new(file).exists() {
+                fs::remove_file(file)?;
+            }
+        }
+
+        self.task_counter.set(0);
+        Ok(())
+    }
+}
+
+// Whether both hold the same tasks, in any order
+fn same_tasks(tasks: &[Task], other_tasks: &[Task]) -> bool {
+    tasks.len() == other_tasks.len() && tasks.iter().all(|task| other_tasks.contains(task))
+}
+
+// There is no archive until the first task gets archived
+fn load_archive() -> Result<LoadedTasks, Box<dyn Error>> {
+    if !Path::new(ARCHIVE_FILE).exists() {
+        return Ok(LoadedTasks {
+            tasks: Vec::new(),
+            errors: Vec::new(),
+        });
+    }
+
+    persistence::load_tasks_from_csv(ARCHIVE_FILE)
+}
+
+// Tables are created on the first connection. Dates are stored as text, the
+// same as in tasks.csv, so tasks read back exactly as they were written.
+const POSTGRES_SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS list_state (
+        id INTEGER PRIMARY KEY CHECK (id = 1),
+        revision BIGINT NOT NULL,
+        task_counter BIGINT NOT NULL
+    );
+    INSERT INTO list_state (id, revision, task_counter) VALUES (1, 0, 0) ON CONFLICT DO NOTHING;
+    CREATE TABLE IF NOT EXISTS tasks (
+        archived BOOLEAN NOT NULL,
+        position BIGINT NOT NULL,
+        id BIGINT NOT NULL,
+        title TEXT NOT NULL,
+        description TEXT,
+        due_date TEXT,
+        status TEXT NOT NULL,
+        deleted_at TEXT,
+        completed_at TEXT,
+        tags TEXT[] NOT NULL,
+        priority TEXT,
+        created_at TEXT,
+        uid TEXT,
+        PRIMARY KEY (archived, position)
+    );
+    CREATE TABLE IF NOT EXISTS events (
+        sequence BIGINT PRIMARY KEY,
+        timestamp TEXT NOT NULL,
+        task_id BIGINT NOT NULL,
+        kind TEXT NOT NULL,
+        field TEXT,
+        old_value TEXT,
+        new_value TEXT
+    );
+    CREATE TABLE IF NOT EXISTS snapshots (
+        sequence BIGINT PRIMARY KEY,
+        taken_at TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS snapshot_tasks (
+        archived BOOLEAN NOT NULL,
+        position BIGINT NOT NULL,
+        id BIGINT NOT NULL,
+        title TEXT NOT NULL,
+        description TEXT,
+        due_date TEXT,
+        status TEXT NOT NULL,
+        deleted_at TEXT,
+        completed_at TEXT,
+        tags TEXT[] NOT NULL,
+        priority TEXT,
+        created_at TEXT,
+        uid TEXT,
+        sequence BIGINT NOT NULL REFERENCES snapshots ON DELETE CASCADE,
+        PRIMARY KEY (sequence, archived, position)
+    );
+";
+const TASK_COLUMNS: &str =
+    "archived, position, id, title, description, due_date, status, deleted_at, completed_at, tags, priority, created_at, uid";
+// Held while tasks are read or written, the same as tasks.csv.lock
+const ADVISORY_LOCK_KEY: i64 = 0x5254_4443;
+
+// Tasks, archived tasks, the event log with its snapshots and the task counter
+// in a Postgres database given by DATABASE_URL. Saves are written in a single
+// transaction and count up the revision.
+pub struct PostgresStorage {
+    // Shared with the lock, which releases the advisory lock when dropped
+    client: Rc<RefCell<Client>>,
+}
+
+impl PostgresStorage {
+    pub fn connect() -> Result<PostgresStorage, Box<dyn Error>> {
+        let url = std::env::var(DATABASE_URL_VARIABLE).map_err(|_| {
+            format!(
+                "{} is not set, set it to the database to use, e.g. postgres://todo@localhost/todo",
+                DATABASE_URL_VARIABLE
+            )
+        })?;
+        let mut client = Client::connect(&url, NoTls)?;
+        client.batch_execute(POSTGRES_SCHEMA)?;
+
+        Ok(PostgresStorage {
+            client: Rc::new(RefCell::new(client)),
+        })
+    }
+}
+
+struct AdvisoryLock {
+    client: Rc<RefCell<Client>>,
+}
+
+impl Drop for AdvisoryLock {
+    fn drop(&mut self) {
+        let _ = self
+            .client
+            .borrow_mut()
+            .execute("SELECT pg_advisory_unlock($1)", &[&ADVISORY_LOCK_KEY]);
+    }
+}
+
+impl Storage for PostgresStorage {
+    fn lock(&self) -> Result<StorageLock, Box<dyn Error>> {
+        let mut client = self.client.borrow_mut();
+
+        let locked: bool = client
+            .query_one("SELECT pg_try_advisory_lock($1)", &[&ADVISORY_LOCK_KEY])?
+            .get(0);
+        if !locked {
+            println!("Waiting for another instance to release the database");
+            client.execute("SELECT pg_advisory_lock($1)", &[&ADVISORY_LOCK_KEY])?;
+        }
+
+        let lock = AdvisoryLock {
+            client: Rc::clone(&self.client),
+        };
+        Ok(StorageLock { _guard: Box::new(lock) })
+    }
+
+    fn revision(&self) -> Result<Revision, Box<dyn Error>> {
+        let row = self.client.borrow_mut().query_one("SELECT revision FROM list_state", &[])?;
+        Ok(Revision::Database(row.get(0)))
+    }
+
+    fn load_list(&self) -> Result<LoadedList, Box<dyn Error>> {
+        let (tasks, archived_tasks) = self.load_tasks()?;
+
+        Ok(LoadedList {
+            tasks,
+            archived_tasks,
+            logged_events: self.event_count()?,
+            task_counter: self.load_task_counter()?,
+            problems: Vec::new(),
+        })
+    }
+
+    fn load_tasks(&self) -> Result<(Vec<Task>, Vec<Task>), Box<dyn Error>> {
+        let query = format!("SELECT {} FROM tasks ORDER BY archived, position", TASK_COLUMNS);
+        let rows = self.client.borrow_mut().query(query.as_str(), &[])?;
+        tasks_from_rows(&rows)
+    }
+
+    // The database keeps no files to copy, the tasks are written to a backup
+    // like tasks.csv and archive.csv would be
+    fn back_up(&self, retention: usize) -> Result<(), Box<dyn Error>> {
+        if self.revision()? == Revision::Database(0) {
+            return Ok(());
+        }
+
+        let (tasks, archived_tasks) = self.load_tasks()?;
+        backup::create_backup_of(retention, &tasks, &archived_tasks)?;
+        Ok(())
+    }
+
+    fn initialize_log(&self, tasks: &[Task], archived_tasks: &[Task]) -> Result<bool, Box<dyn Error>> {
+        let mut client = self.client.borrow_mut();
+        let snapshots: i64 = client.query_one("SELECT count(*) FROM snapshots", &[])?.get(0);
+        if snapshots > 0 {
+            return Ok(false);
+        }
+
+        let mut transaction = client.transaction()?;
+        insert_snapshot(&mut transaction, 0, tasks, archived_tasks)?;
+        transaction.commit()?;
+        Ok(true)
+    }
+
+    fn write_tasks(
+        &self,
+        tasks: &[Task],
+        archived_tasks: &[Task],
+        events: &[Event],
+        logged_events: usize,
+        task_counter: usize,
+    ) -> Result<usize, Box<dyn Error>> {
+        let mut client = self.client.borrow_mut();
+        let mut transaction = client.transaction()?;
+
+        transaction.execute("DELETE FROM tasks", &[])?;
+        insert_tasks(&mut transaction, None, tasks, archived_tasks)?;
+
+        insert_events(&mut transaction, logged_events, events)?;
+        let total_events = logged_events + events.len();
+        if event_log::snapshot_due(logged_events, total_events) {
+            insert_snapshot(&mut transaction, total_events, tasks, archived_tasks)?;
+        }
+
+        transaction.execute(
+            "UPDATE list_state SET revision = revision + 1, task_counter = GREATEST(task_counter, $1)",
+            &[&(task_counter as i64)],
+        )?;
+        transaction.commit()?;
+
+        Ok(total_events)
+    }
+
+    fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>> {
+        let rows = self.client.borrow_mut().query(
+            "SELECT timestamp, task_id, kind, field, old_value, new_value FROM events ORDER BY sequence",
+            &[],
+        )?;
+
+        let mut events = Vec::new();
+        for row in rows {
+            let timestamp: String = row.get("timestamp");
+            let kind: String = row.get("kind");
+            let task_id: i64 = row.get("task_id");
+            events.push(Event {
+                timestamp: NaiveDateTimeWrapper::from_str(&timestamp)
+                    .map_err(|_| format!("invalid event timestamp: {}", timestamp))?,
+                task_id: task_id as usize,
+                kind: EventKind::from_str(&kind).map_err(|_| format!("invalid event kind: {}", kind))?,
+                field: row.get("field"),
+                old_value: row.get("old_value"),
+                new_value: row.get("new_value"),
+            });
+        }
+
+        Ok(events)
+    }
+
+    fn event_count(&self) -> Result<usize, Box<dyn Error>> {
+        let events: i64 = self.client.borrow_mut().query_one("SELECT count(*) FROM events", &[])?.get(0);
+        Ok(events as usize)
+    }
+
+    fn load_snapshot(
+        &self,
+        max_sequence: usize,
+        until: Option<NaiveDateTime>,
+    ) -> Result<Option<LoadedSnapshot>, Box<dyn Error>> {
+        let mut client = self.client.borrow_mut();
+
+        // Snapshot times are written in a format that sorts like the times do
+        let until = until.map(|until| date_text(&NaiveDateTimeWrapper(until)));
+        let row = client.query_opt(
+            "SELECT sequence FROM snapshots WHERE sequence <= $1 AND ($2::TEXT IS NULL OR taken_at <= $2)
+             ORDER BY sequence DESC LIMIT 1",
+            &[&(max_sequence as i64), &until],
+        )?;
+        let sequence: i64 = match row {
+            Some(row) => row.get(0),
+            None => return Ok(None),
+        };
+
+        let query = format!(
+            "SELECT {} FROM snapshot_tasks WHERE sequence = $1 ORDER BY archived, position",
+            TASK_COLUMNS
+        );
+        let (tasks, archived_tasks) = tasks_from_rows(&client.query(query.as_str(), &[&sequence])?)?;
+
+        Ok(Some(LoadedSnapshot {
+            sequence: sequence as usize,
+            tasks,
+            archived_tasks,
+        }))
+    }
+
+    fn load(&self) -> Result<StoredData, Box<dyn Error>> {
+        let (tasks, archived_tasks) = self.load_tasks()?;
+
+        Ok(StoredData {
+            tasks,
+            archived_tasks,
+            task_counter: self.load_task_counter()?,
+            events: self.load_events()?,
+        })
+    }
+
+    fn save(&self, data: &StoredData) -> Result<(), Box<dyn Error>> {
+        let mut client = self.client.borrow_mut();
+        let mut transaction = client.transaction()?;
+
+        transaction.batch_execute("DELETE FROM tasks; DELETE FROM events; DELETE FROM snapshots;")?;
+        insert_tasks(&mut transaction, None, &data.tasks, &data.archived_tasks)?;
+        insert_events(&mut transaction, 0, &data.events)?;
+        insert_snapshot(&mut transaction, data.events.len(), &data.tasks, &data.archived_tasks)?;
+        transaction.execute(
+            "UPDATE list_state SET revision = revision + 1, task_counter = $1",
+            &[&(data.task_counter as i64)],
+        )?;
+
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), Box<dyn Error>> {
+        let mut client = self.client.borrow_mut();
+        let mut transaction = client.transaction()?;
+
+        // Snapshot tasks are deleted with their snapshots
+        transaction.batch_execute(
+            "DELETE FROM tasks; DELETE FROM events; DELETE FROM snapshots;
+             UPDATE list_state SET revision = revision + 1, task_counter = 0;",
+        )?;
+
+        transaction.commit()?;
+        Ok(())
+    }
+}
+
+impl PostgresStorage {
+    fn load_task_counter(&self) -> Result<usize, Box<dyn Error>> {
+        let row = self.client.borrow_mut().query_one("SELECT task_counter FROM list_state", &[])?;
+        let task_counter: i64 = row.get(0);
+        Ok(task_counter as usize)
+    }
+}
+
+fn date_text(date: &NaiveDateTimeWrapper) -> String {
+    date.0.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+// Into tasks, or into snapshot_tasks for the snapshot taken after sequence events
+fn insert_tasks(
+    transaction: &mut Transaction,
+    sequence: Option<i64>,
+    tasks: &[Task],
+    archived_tasks: &[Task],
+) -> Result<(), Box<dyn Error>> {
+    let statement = match sequence {
+        Some(_) => format!(
+            "INSERT INTO snapshot_tasks ({}, sequence) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)",
+            TASK_COLUMNS
+        ),
+        None => format!(
+            "INSERT INTO tasks ({}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)",
+            TASK_COLUMNS
+        ),
+    };
+    let statement = transaction.prepare(&statement)?;
+
+    let all_tasks = tasks.iter().map(|task| (false, task)).chain(archived_tasks.iter().map(|task| (true, task)));
+    for (position, (archived, task)) in all_tasks.enumerate() {
+        let position = position as i64;
+        let id = task.id as i64;
+        let status = task.status.to_string();
+        let dates = [task.due_date, task.deleted_at, task.completed_at, task.created_at].map(|d| d.as_ref().map(date_text));
+        let priority = task.priority.map(|p| p.to_string());
+
+        let mut values: Vec<&(dyn ToSql + Sync)> = vec![
+            &archived,
+            &position,
+            &id,
+            &task.title,
+            &task.description,
+            &dates[0],
+            &status,
+            &dates[1],
+            &dates[2],
+            &task.tags,
+            &priority,
+            &dates[3],
+            &task.uid,
+        ];
+        if let Some(sequence) = &sequence {
+            values.push(sequence);
+        }
+        transaction.execute(&statement, &values)?;
+    }
+
+    Ok(())
+}
+
+// Events are numbered from 1 in the order they were logged
+fn insert_events(transaction: &mut Transaction, logged_events: usize, events: &[Event]) -> Result<(), Box<dyn Error>> {
+    let statement = transaction.prepare(
+        "INSERT INTO events (sequence, timestamp, task_id, kind, field, old_value, new_value)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)",
+    )?;
+
+    for (index, event) in events.iter().enumerate() {
+        transaction.execute(
+            &statement,
+            &[
+                &((logged_events + index + 1) as i64),
+                &date_text(&event.timestamp),
+                &(event.task_id as i64),
+                &event.kind.to_string(),
+                &event.field,
+                &event.old_value,
+                &event.new_value,
+            ],
+        )?;
+    }
+
+    Ok(())
+}
+
+fn insert_snapshot(
+    transaction: &mut Transaction,
+    sequence: usize,
+    tasks: &[Task],
+    archived_tasks: &[Task],
+) -> Result<(), Box<dyn Error>> {
+    let sequence = sequence as i64;
+    transaction.execute(
+        "INSERT INTO snapshots (sequence, taken_at) VALUES ($1, $2)",
+        &[&sequence, &date_text(&NaiveDateTimeWrapper::now())],
+    )?;
+
+    insert_tasks(transaction, Some(sequence), tasks, archived_tasks)
+}
+
+fn tasks_from_rows(rows: &[Row]) -> Result<(Vec<Task>, Vec<Task>), Box<dyn Error>> {
+    let mut tasks = Vec::new();
+    let mut archived_tasks = Vec::new();
+
+    for row in rows {
+        let task = task_from_row(row)?;
+        if row.get::<_, bool>("archived") {
+            archived_tasks.push(task);
+        } else {
+            tasks.push(task);
+        }
+    }
+
+    Ok((tasks, archived_tasks))
+}
+
+fn task_from_row(row: &Row) -> Result<Task, String> {
+    let date = |column: &str| {
+        row.get::<_, Option<String>>(column)
+            .map(|date| NaiveDateTimeWrapper::from_str(&date).map_err(|_| format!("invalid {}: {}", column, date)))
+            .transpose()
+    };
+
+    let id: i64 = row.get("id");
+    let mut task = Task::new(id as usize, row.get("title"));
+    task.description = row.get("description");
+    task.due_date = date("due_date")?;
+    let status: String = row.get("status");
+    task.set_status(TaskStatus::from_str(&status).map_err(|_| format!("invalid status: {}", status))?);
+    task.set_deleted_at(date("deleted_at")?);
+    task.set_completed_at(date("completed_at")?);
+    task.set_tags(row.get("tags"));
+    let priority = row
+        .get::<_, Option<String>>("priority")
+        .map(|priority| Priority::from_str(&priority).map_err(|_| format!("invalid priority: {}", priority)))
+        .transpose()?;
+    task.set_priority(priority);
+    task.set_created_at(date("created_at")?);
+    task.set_uid(row.get("uid"));
+
+    Ok(task)
+}
+
+#[derive(Debug)]
+pub struct CopyReport {
+    pub tasks: usize,
+    pub archived_tasks: usize,
+    pub events: usize,
+    pub task_counter: usize,
+}
+
+// Moves everything from one backend to another that holds nothing yet. The
+// source is backed up, the copy is read back from the target and checked that
+// nothing was lost or changed, then the source is emptied so tasks can be
+// moved back to it later.
+pub fn copy_data(from: &dyn Storage, to: &dyn Storage, backup_retention: usize) -> Result<CopyReport, Box<dyn Error>> {
+    let _from_lock = from.lock()?;
+    let _to_lock = to.lock()?;
+    let data = from.load()?;
+
+    if !to.load()?.is_empty() {
+        return Err("the target already holds tasks, they would be overwritten".into());
+    }
+
+    // Made even with backups turned off, the source won't have the tasks anymore
+    from.back_up(backup_retention.max(1))?;
+    to.save(&data)?;
+    let copied = to.load()?;
+    verify_copy(&data, &copied).map_err(|e| format!("the copy could not be verified: {}", e))?;
+    from.clear()
+        .map_err(|e| format!("the tasks were copied, but could not be removed from where they were: {}", e))?;
+
+    Ok(CopyReport {
+        tasks: data.tasks.len(),
+        archived_tasks: data.archived_tasks.len(),
+        events: data.events.len(),
+        task_counter: data.task_counter,
+    })
+}
+
+fn verify_copy(expected: &StoredData, actual: &StoredData) -> Result<(), String> {
+    verify_tasks("tasks", &expected.tasks, &actual.tasks)?;
+    verify_tasks("archived tasks", &expected.archived_tasks, &actual.archived_tasks)?;
+
+    if expected.events.len() != actual.events.len() {
+        return Err(format!(
+            "{} events were copied, {} were read back",
+            expected.events.len(),
+            actual.events.len()
+        ));
+    }
+    if let Some(index) = expected.events.iter().zip(&actual.events).position(|(e, a)| e != a) {
+        return Err(format!("event {} differs after the copy", index + 1));
+    }
+
+    if expected.task_counter != actual.task_counter {
+        return Err(format!(
+            "the task counter was {}, it was read back as {}",
+            expected.task_counter, actual.task_counter
+        ));
+    }
+
+    Ok(())
+}
+
+// Backends may return tasks in any order
+fn verify_tasks(name: &str, expected: &[Task], actual: &[Task]) -> Result<(), String> {
+    if expected.len() != actual.len() {
+        return Err(format!("{} {} were copied, {} were read back", expected.len(), name, actual.len()));
+    }
+
+    for task in expected {
+        match actual.iter().find(|t| t.id == task.id) {
+            Some(copied_task) if copied_task == task => {}
+            Some(_) => return Err(format!("task {} differs after the copy", task.id)),
+            None => return Err(format!("task {} is missing after the copy", task.id)),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Keeps everything in memory, lose_events makes saves drop the event log
+    #[derive(Default)]
+    struct MemoryStorage {
+        data: RefCell<StoredData>,
+        backups: Cell<usize>,
+        lose_events: bool,
+    }
+
+    impl MemoryStorage {
+        fn with_tasks(titles: &[&str]) -> MemoryStorage {
+            let tasks: Vec<Task> = titles
+                .iter()
+                .enumerate()
+                .map(|(index, title)| Task::new(index + 1, title.to_string()))
+                .collect();
+            let events = tasks
+                .iter()
+                .map(|task| Event {
+                    timestamp: NaiveDateTimeWrapper::now(),
+                    task_id: task.id,
+                    kind: EventKind::Created,
+                    field: None,
+                    old_value: None,
+                    new_value: Some(task.title.clone()),
+                })
+                .collect();
+
+            MemoryStorage {
+                data: RefCell::new(StoredData {
+                    task_counter: tasks.len(),
+                    archived_tasks: vec![Task::new(tasks.len() + 1, "Archived".to_string())],
+                    tasks,
+                    events,
+                }),
+                ..MemoryStorage::default()
+            }
+        }
+    }
+
+    impl Storage for MemoryStorage {
+        fn lock(&self) -> Result<StorageLock, Box<dyn Error>> {
+            Ok(StorageLock { _guard: Box::new(()) })
+        }
+
+        fn revision(&self) -> Result<Revision, Box<dyn Error>> {
+            Ok(Revision::Database(0))
+        }
+
+        fn load_list(&self) -> Result<LoadedList, Box<dyn Error>> {
+            unimplemented!()
+        }
+
+        fn load_tasks(&self) -> Result<(Vec<Task>, Vec<Task>), Box<dyn Error>> {
+            let data = self.data.borrow();
+            Ok((data.tasks.clone(), data.archived_tasks.clone()))
+        }
+
+        fn back_up(&self, retention: usize) -> Result<(), Box<dyn Error>> {
+            assert!(retention > 0);
+            self.backups.set(self.backups.get() + 1);
+            Ok(())
+        }
+
+        fn initialize_log(&self, _tasks: &[Task], _archived_tasks: &[Task]) -> Result<bool, Box<dyn Error>> {
+            unimplemented!()
+        }
+
+        fn write_tasks(
+            &self,
+            _tasks: &[Task],
+            _archived_tasks: &[Task],
+            _events: &[Event],
+            _logged_events: usize,
+            _task_counter: usize,
+        ) -> Result<usize, Box<dyn Error>> {
+            unimplemented!()
+        }
+
+        fn load_events(&self) -> Result<Vec<Event>, Box<dyn Error>> {
+            Ok(self.data.borrow().events.clone())
+        }
+
+        fn load_snapshot(
+            &self,
+            _max_sequence: usize,
+            _until: Option<NaiveDateTime>,
+        ) -> Result<Option<LoadedSnapshot>, Box<dyn Error>> {
+            Ok(None)
+        }
+
+        fn load(&self) -> Result<StoredData, Box<dyn Error>> {
+            let data = self.data.borrow();
+            Ok(StoredData {
+                tasks: data.tasks.clone(),
+                archived_tasks: data.archived_tasks.clone(),
+                task_counter: data.task_counter,
+                events: data.events.clone(),
+            })
+        }
+
+        fn save(&self, data: &StoredData) -> Result<(), Box<dyn Error>> {
+            *self.data.borrow_mut() = StoredData {
+                tasks: data.tasks.clone(),
+                archived_tasks: data.archived_tasks.clone(),
+                task_counter: data.task_counter,
+                events: if self.lose_events { Vec::new() } else { data.events.clone() },
+            };
+            Ok(())
+        }
+
+        fn clear(&self) -> Result<(), Box<dyn Error>> {
+            *self.data.borrow_mut() = StoredData::default();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn moves_tasks_and_empties_the_source() {
+        let csv = MemoryStorage::with_tasks(&["Write report", "Call back"]);
+        let postgres = MemoryStorage::default();
+
+        let report = copy_data(&csv, &postgres, 0).unwrap();
+
+        assert_eq!((report.tasks, report.archived_tasks, report.events, report.task_counter), (2, 1, 2, 2));
+        assert_eq!(postgres.data.borrow().tasks[1].title, "Call back");
+        assert!(csv.data.borrow().is_empty());
+        assert_eq!(csv.backups.get(), 1);
+
+        // The emptied source can take the tasks back
+        copy_data(&postgres, &csv, 10).unwrap();
+        assert_eq!(csv.data.borrow().tasks.len(), 2);
+        assert_eq!(csv.data.borrow().task_counter, 2);
+        assert!(postgres.data.borrow().is_empty());
+    }
+
+    #[test]
+    fn refuses_to_overwrite_tasks_in_the_target() {
+        let csv = MemoryStorage::with_tasks(&["Write report"]);
+        let postgres = MemoryStorage::with_tasks(&["Other task"]);
+
+        assert!(copy_data(&csv, &postgres, 10).is_err());
+        assert_eq!(csv.data.borrow().tasks[0].title, "Write report");
+        assert_eq!(postgres.data.borrow().tasks[0].title, "Other task");
+    }
+
+    #[test]
+    fn keeps_the_source_when_the_copy_differs() {
+        let csv = MemoryStorage::with_tasks(&["Write report"]);
+        let postgres = MemoryStorage {
+            lose_events: true,
+            ..MemoryStorage::default()
+        };
+
+        let error = copy_data(&csv, &postgres, 10).unwrap_err();
+
+        assert!(error.to_string().contains("could not be verified"));
+        assert_eq!(csv.data.borrow().tasks.len(), 1);
+        assert_eq!(csv.data.borrow().events.len(), 1);
+    }
+}
@@ -2,17 +2,17 @@ use std::str::FromStr;
 
 use serde_derive::{Serialize, Deserialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum SupportedPersistence {
     CSV,
     Postgres,
 }
 
-impl ToString for SupportedPersistence {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for SupportedPersistence {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            SupportedPersistence::CSV => "CSV".to_string(),
-            SupportedPersistence::Postgres => "Postgres".to_string(),
+            SupportedPersistence::CSV => write!(f, "CSV"),
+            SupportedPersistence::Postgres => write!(f, "Postgres"),
         }
     }
 }
@@ -0,0 +1,299 @@
+use std::str::FromStr;
+
+use colorize::AnsiColor;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::priority::Priority;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+// Used when the output is not a terminal (e.g. piped to a file)
+const DEFAULT_TERMINAL_WIDTH: usize = 80;
+// Flexible columns are never shrunk below this width
+const MIN_FLEXIBLE_WIDTH: usize = 10;
+const COLUMN_SEPARATOR: &str = "  ";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ListLayout {
+    // One line per task with the configured columns
+    Table,
+    // A block of lines per task with all details
+    Detailed,
+}
+
+impl std::fmt::Display for ListLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ListLayout::Table => write!(f, "table"),
+            ListLayout::Detailed => write!(f, "detailed"),
+        }
+    }
+}
+
+impl FromStr for ListLayout {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ListLayout, Self::Err> {
+        match input.to_lowercase().trim() {
+            "table" => Ok(ListLayout::Table),
+            "detailed" => Ok(ListLayout::Detailed),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Column {
+    Id,
+    Title,
+    Description,
+    Status,
+    Due,
+    Completed,
+    Tags,
+    Priority,
+}
+
+impl Column {
+    pub fn all() -> Vec<Column> {
+        vec![
+            Column::Id,
+            Column::Title,
+            Column::Description,
+            Column::Status,
+            Column::Due,
+            Column::Completed,
+            Column::Tags,
+            Column::Priority,
+        ]
+    }
+
+    pub fn default_columns() -> Vec<Column> {
+        vec![Column::Id, Column::Title, Column::Status, Column::Due]
+    }
+
+    // Parses a comma separated list of column names, e.g. "id,title,due"
+    pub fn parse_list(input: &str) -> Result<Vec<Column>, String> {
+        input
+            .split(',')
+            .map(|name| name.trim())
+            .filter(|name| !name.is_empty())
+            .map(|name| Column::from_str(name).map_err(|_| format!("Unknown column: {}", name)))
+            .collect()
+    }
+
+    fn header(&self) -> &'static str {
+        match self {
+            Column::Id => "Id",
+            Column::Title => "Title",
+            Column::Description => "Description",
+            Column::Status => "Status",
+            Column::Due => "Due",
+            Column::Completed => "Completed",
+            Column::Tags => "Tags",
+            Column::Priority => "Priority",
+        }
+    }
+
+    // Flexible columns share the width left over by the others
+    fn is_flexible(&self) -> bool {
+        matches!(self, Column::Title | Column::Description)
+    }
+
+    fn value(&self, task: &Task) -> String {
+        match self {
+            Column::Id => task.id.to_string(),
+            Column::Title => task.title.clone(),
+            Column::Description => task.description.clone().unwrap_or_default(),
+            Column::Status => task.status.to_string(),
+            Column::Due => task
+                .due_date
+                .map(|d| d.0.format("%d.%m.%Y %H:%M").to_string())
+                .unwrap_or_default(),
+            Column::Completed => task
+                .completed_at
+                .map(|d| d.0.format("%d.%m.%Y %H:%M").to_string())
+                .unwrap_or_default(),
+            Column::Tags => task
+                .tags
+                .iter()
+                .map(|tag| format!("+{}", tag))
+                .collect::<Vec<String>>()
+                .join(" "),
+            Column::Priority => task.priority.map(|p| p.to_string()).unwrap_or_default(),
+        }
+    }
+
+    fn colorize(&self, task: &Task, text: String) -> String {
+        match self {
+            Column::Status => match task.status {
+                TaskStatus::NotStarted => text,
+                TaskStatus::InProgress => text.yellow(),
+                TaskStatus::Completed => text.green(),
+            },
+            Column::Due if task.is_overdue() => text.red(),
+            Column::Tags => text.cyan(),
+            Column::Priority => match task.priority {
+                Some(Priority::High) => text.red(),
+                Some(Priority::Medium) => text.yellow(),
+                _ => text,
+            },
+            _ => text,
+        }
+    }
+}
+
+impl std::fmt::Display for Column {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.header().to_lowercase())
+    }
+}
+
+impl FromStr for Column {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Column, Self::Err> {
+        match input.to_lowercase().trim() {
+            "id" => Ok(Column::Id),
+            "title" => Ok(Column::Title),
+            "description" => Ok(Column::Description),
+            "status" => Ok(Column::Status),
+            "due" => Ok(Column::Due),
+            "completed" => Ok(Column::Completed),
+            "tags" => Ok(Column::Tags),
+            "priority" => Ok(Column::Priority),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn terminal_width() -> usize {
+    ratatui::crossterm::terminal::size()
+        .map(|(width, _)| width as usize)
+        .unwrap_or(DEFAULT_TERMINAL_WIDTH)
+}
+
+// Fixed columns get the width of their longest value, flexible columns split
+// whatever is left of the terminal width
+fn column_widths(tasks: &[&Task], columns: &[Column], total_width: usize) -> Vec<usize> {
+    let natural_widths: Vec<usize> = columns
+        .iter()
+        .map(|column| {
+            tasks
+                .iter()
+                .map(|task| column.value(task).chars().count())
+                .chain(std::iter::once(column.header().len()))
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+
+    let separators = COLUMN_SEPARATOR.len() * columns.len().saturating_sub(1);
+    let fixed_width: usize = columns
+        .iter()
+        .zip(&natural_widths)
+        .filter(|(column, _)| !column.is_flexible())
+        .map(|(_, width)| width)
+        .sum();
+    let flexible_count = columns.iter().filter(|c| c.is_flexible()).count();
+
+    let available = total_width.saturating_sub(fixed_width + separators);
+    let flexible_width = (available / flexible_count.max(1)).max(MIN_FLEXIBLE_WIDTH);
+
+    columns
+        .iter()
+        .zip(natural_widths)
+        .map(|(column, width)| {
+            if column.is_flexible() {
+                width.min(flexible_width)
+            } else {
+                width
+            }
+        })
+        .collect()
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+
+    let mut truncated: String = text.chars().take(width.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+// Splits text into lines of at most width characters, breaking at spaces where possible
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+
+    for word in text.split_whitespace() {
+        let line_length = line.chars().count();
+        if line_length > 0 && line_length + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+
+        while line.chars().count() > width {
+            let rest: String = line.chars().skip(width).collect();
+            lines.push(line.chars().take(width).collect());
+            line = rest;
+        }
+    }
+
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn pad(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(text.chars().count());
+    format!("{}{}", text, " ".repeat(padding))
+}
+
+// Prints tasks as a table fitted to width. Values that don't fit flexible
+// columns are either wrapped onto more lines or truncated.
+pub fn print_tasks_table(tasks: &[&Task], columns: &[Column], width: usize, wrap_values: bool) {
+    let widths = column_widths(tasks, columns, width);
+
+    let header: Vec<String> = columns
+        .iter()
+        .zip(&widths)
+        .map(|(column, width)| pad(column.header(), *width))
+        .collect();
+    println!("{}", header.join(COLUMN_SEPARATOR).trim_end().to_string().bold());
+
+    for task in tasks {
+        let cells: Vec<Vec<String>> = columns
+            .iter()
+            .zip(&widths)
+            .map(|(column, width)| {
+                let value = column.value(task);
+                if wrap_values && column.is_flexible() {
+                    wrap(&value, *width)
+                } else {
+                    vec![truncate(&value, *width)]
+                }
+            })
+            .collect();
+
+        let height = cells.iter().map(|lines| lines.len()).max().unwrap_or(1);
+        for line_index in 0..height {
+            let line: Vec<String> = columns
+                .iter()
+                .zip(&widths)
+                .zip(&cells)
+                .map(|((column, width), lines)| {
+                    let text = lines.get(line_index).cloned().unwrap_or_default();
+                    column.colorize(task, pad(&text, *width))
+                })
+                .collect();
+            println!("{}", line.join(COLUMN_SEPARATOR).trim_end());
+        }
+    }
+}
@@ -1,15 +1,27 @@
-use serde_derive::Serialize;
+use serde_derive::{Deserialize, Serialize};
 
 use crate::TaskStatus;
+use crate::priority::Priority;
 use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
 pub struct Task {
     pub id: usize,
     pub title: String,
     pub description: Option<String>,
     pub due_date: Option<NaiveDateTimeWrapper>,
     pub status: TaskStatus,
+    // Set when the task is moved to trash
+    pub deleted_at: Option<NaiveDateTimeWrapper>,
+    // Set when the task moves to TaskStatus::Completed
+    pub completed_at: Option<NaiveDateTimeWrapper>,
+    #[serde(with = "tags_format")]
+    pub tags: Vec<String>,
+    pub priority: Option<Priority>,
+    // Missing for tasks created before it was recorded
+    pub created_at: Option<NaiveDateTimeWrapper>,
+    // UID of the calendar item the task was imported from
+    pub uid: Option<String>,
 }
 
 impl Task {
@@ -20,9 +32,26 @@ impl Task {
             description: None,
             due_date: None,
             status: TaskStatus::NotStarted,
+            deleted_at: None,
+            completed_at: None,
+            tags: Vec::new(),
+            priority: None,
+            created_at: None,
+            uid: None,
         }
     }
-    
+
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+
+    pub fn is_overdue(&self) -> bool {
+        self.status != TaskStatus::Completed
+            && self
+                .due_date
+                .is_some_and(|due_date| due_date.0 < NaiveDateTimeWrapper::now().0)
+    }
+
     pub fn set_description(&mut self, description: String) {
         self.description = Some(description);
     }
@@ -34,6 +63,57 @@ impl Task {
     pub fn set_status(&mut self, status: TaskStatus) {
         self.status = status;
     }
+
+    pub fn set_deleted_at(&mut self, deleted_at: Option<NaiveDateTimeWrapper>) {
+        self.deleted_at = deleted_at;
+    }
+
+    pub fn set_completed_at(&mut self, completed_at: Option<NaiveDateTimeWrapper>) {
+        self.completed_at = completed_at;
+    }
+
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags = tags;
+    }
+
+    pub fn set_priority(&mut self, priority: Option<Priority>) {
+        self.priority = priority;
+    }
+
+    pub fn set_created_at(&mut self, created_at: Option<NaiveDateTimeWrapper>) {
+        self.created_at = created_at;
+    }
+
+    pub fn set_uid(&mut self, uid: Option<String>) {
+        self.uid = uid;
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+}
+
+// Tags are kept in a single space separated column so the CSV stays flat
+mod tags_format {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S>(tags: &[String], serializer: S) -> Result<S::Ok, S::Error>
+    where S: Serializer {
+        serializer.serialize_str(&tags.join(" "))
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    where D: Deserializer<'de> {
+        let tags = String::deserialize(deserializer)?;
+        Ok(super::parse_tags(&tags))
+    }
+}
+
+pub fn parse_tags(tags: &str) -> Vec<String> {
+    tags.split_whitespace()
+        .map(|tag| tag.trim_start_matches('+').to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
 }
 
 impl Clone for Task {
@@ -44,6 +124,12 @@ impl Clone for Task {
             description: self.description.clone(),
             due_date: self.due_date,
             status: self.status.clone(),
+            deleted_at: self.deleted_at,
+            completed_at: self.completed_at,
+            tags: self.tags.clone(),
+            priority: self.priority,
+            created_at: self.created_at,
+            uid: self.uid.clone(),
         }
     }
 }
@@ -1,28 +1,50 @@
 use std::error::Error;
 
+use crate::event_log::{self, Event};
+use crate::history::{Change, History, Operation};
+use crate::merge::{self, MergeReport};
 use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::priority::Priority;
+use crate::persistence::SaveError;
+use crate::storage::{Revision, Storage};
 use crate::task::Task;
-use crate::{persistence, TaskStatus};
+use crate::TaskStatus;
 
 // THINK: Maybe use a hashmap instead of a vector?
 // WHY: We might use a hashmap because we want to be able to access tasks by id.
 // WHY NOT: We're using a vector because we want to preserve the order of the tasks.
 pub struct TaskList {
     pub tasks: Vec<Task>,
+    // Tasks moved out of the list, stored separately to keep tasks.csv small
+    pub archived_tasks: Vec<Task>,
+    pub history: History,
     task_counter: usize,
-}
-
-impl Default for TaskList {
-    fn default() -> Self {
-        TaskList::new()
-    }
+    // Where tasks are loaded from and saved to
+    storage: Box<dyn Storage>,
+    // Tasks and their revision as last loaded or saved, used to detect and
+    // merge changes made by other instances working on the same tasks
+    stored_tasks: Vec<Task>,
+    stored_archived_tasks: Vec<Task>,
+    stored_revision: Option<Revision>,
+    // Events for changes made since the last save, appended to the event log on save
+    pending_events: Vec<Event>,
+    // Number of events in the log as of the last load or save
+    logged_events: usize,
 }
 
 impl TaskList {
-    pub fn new() -> TaskList {
+    pub fn new(storage: Box<dyn Storage>) -> TaskList {
         TaskList {
             tasks: Vec::new(),
+            archived_tasks: Vec::new(),
+            history: History::new(),
             task_counter: 0,
+            storage,
+            stored_tasks: Vec::new(),
+            stored_archived_tasks: Vec::new(),
+            stored_revision: None,
+            pending_events: Vec::new(),
+            logged_events: 0,
         }
     }
 
@@ -35,66 +57,474 @@ impl TaskList {
             description: None,
             due_date: None,
             status: TaskStatus::NotStarted,
+            deleted_at: None,
+            completed_at: None,
+            tags: Vec::new(),
+            priority: None,
+            created_at: Some(NaiveDateTimeWrapper::now()),
+            uid: None,
         };
 
+        self.record(Change::Added {
+            index: self.tasks.len(),
+            task: task.clone(),
+        });
         self.tasks.push(task);
 
         self.task_counter
     }
 
+    pub fn update_task_title(&mut self, task_id: usize, title: String) {
+        self.update_task(task_id, |task| task.title = title);
+    }
+
     pub fn update_task_description(&mut self, task_id: usize, description: String) {
+        self.update_task(task_id, |task| task.set_description(description));
+    }
+
+    pub fn update_task_due_date(&mut self, task_id: usize, due_date: NaiveDateTimeWrapper) {
+        self.update_task(task_id, |task| task.set_due_date(due_date));
+    }
+
+    pub fn clear_task_description(&mut self, task_id: usize) {
+        self.update_task(task_id, |task| task.description = None);
+    }
+
+    pub fn clear_task_due_date(&mut self, task_id: usize) {
+        self.update_task(task_id, |task| task.due_date = None);
+    }
+
+    pub fn update_task_status(&mut self, task_id: usize, status: TaskStatus) {
+        self.update_task(task_id, |task| {
+            if task.status != status {
+                let completed_at = match status {
+                    TaskStatus::Completed => Some(NaiveDateTimeWrapper::now()),
+                    _ => None,
+                };
+                task.set_completed_at(completed_at);
+            }
+            task.set_status(status);
+        });
+    }
+
+    // Sets when a task was completed, e.g. to a time from an imported file
+    pub fn update_task_completed_at(&mut self, task_id: usize, completed_at: Option<NaiveDateTimeWrapper>) {
+        self.update_task(task_id, |task| task.set_completed_at(completed_at));
+    }
+
+    pub fn update_task_created_at(&mut self, task_id: usize, created_at: Option<NaiveDateTimeWrapper>) {
+        self.update_task(task_id, |task| task.set_created_at(created_at));
+    }
+
+    pub fn update_task_tags(&mut self, task_id: usize, tags: Vec<String>) {
+        self.update_task(task_id, |task| task.set_tags(tags));
+    }
+
+    pub fn update_task_priority(&mut self, task_id: usize, priority: Option<Priority>) {
+        self.update_task(task_id, |task| task.set_priority(priority));
+    }
+
+    pub fn update_task_uid(&mut self, task_id: usize, uid: Option<String>) {
+        self.update_task(task_id, |task| task.set_uid(uid));
+    }
+
+    // Applies update to the task and records the change for undo
+    fn update_task<F>(&mut self, task_id: usize, update: F)
+    where
+        F: FnOnce(&mut Task),
+    {
         let task = self.tasks.iter_mut().find(|t| t.id == task_id);
 
         if let Some(task) = task {
-            task.set_description(description);
+            let before = task.clone();
+            update(task);
+            let after = task.clone();
+
+            if after != before {
+                self.record(Change::Updated { before, after });
+            }
         }
     }
 
-    pub fn update_task_due_date(&mut self, task_id: usize, due_date: NaiveDateTimeWrapper) {
-        let task = self.tasks.iter_mut().find(|t| t.id == task_id);
+    // Moves the task to trash, where it stays until restored or purged
+    pub fn delete_task(&mut self, task_id: usize) -> Option<Task> {
+        let task = self.get_active_task_by_id(task_id)?.clone();
+        self.update_task(task_id, |task| task.set_deleted_at(Some(NaiveDateTimeWrapper::now())));
 
-        if let Some(task) = task {
-            task.set_due_date(due_date);
+        Some(task)
+    }
+
+    pub fn restore_task(&mut self, task_id: usize) -> Option<Task> {
+        let task = self.trashed_tasks().find(|t| t.id == task_id)?.clone();
+        self.update_task(task_id, |task| task.set_deleted_at(None));
+
+        Some(task)
+    }
+
+    // Permanently removes all tasks in trash, returns how many were removed
+    pub fn empty_trash(&mut self) -> usize {
+        self.purge_trash(|_| true)
+    }
+
+    // Permanently removes tasks that have been in trash for longer than days
+    pub fn purge_trash_older_than(&mut self, days: u32) -> usize {
+        let cutoff = NaiveDateTimeWrapper::now().0 - chrono::Duration::days(days.into());
+
+        self.purge_trash(|deleted_at| deleted_at.0 < cutoff)
+    }
+
+    fn purge_trash<F>(&mut self, should_purge: F) -> usize
+    where
+        F: Fn(&NaiveDateTimeWrapper) -> bool,
+    {
+        let mut purged = 0;
+
+        self.begin_operation();
+        while let Some(task_index) = self
+            .tasks
+            .iter()
+            .position(|t| t.deleted_at.as_ref().is_some_and(&should_purge))
+        {
+            let task = self.tasks.remove(task_index);
+            self.record(Change::Deleted {
+                index: task_index,
+                task,
+            });
+            purged += 1;
         }
+        self.end_operation();
+
+        purged
     }
 
-    pub fn update_task_status(&mut self, task_id: usize, status: TaskStatus) {
-        let task = self.tasks.iter_mut().find(|t| t.id == task_id);
+    // Moves a completed task from the list to the archive
+    pub fn archive_task(&mut self, task_id: usize) -> Option<Task> {
+        let task_index = self
+            .tasks
+            .iter()
+            .position(|t| t.id == task_id && !t.is_deleted() && t.status == TaskStatus::Completed)?;
 
-        if let Some(task) = task {
-            task.set_status(status);
+        Some(self.archive_task_at(task_index))
+    }
+
+    fn archive_task_at(&mut self, task_index: usize) -> Task {
+        let task = self.tasks.remove(task_index);
+
+        self.archived_tasks.push(task.clone());
+        self.record(Change::Archived {
+            index: task_index,
+            task: task.clone(),
+        });
+
+        task
+    }
+
+    // Archives all completed tasks, returns how many were archived
+    pub fn archive_completed_tasks(&mut self) -> usize {
+        self.archive_completed_tasks_where(|_| true)
+    }
+
+    // Archives tasks that have been completed for longer than days
+    pub fn archive_tasks_completed_before(&mut self, days: u32) -> usize {
+        let cutoff = NaiveDateTimeWrapper::now().0 - chrono::Duration::days(days.into());
+
+        self.archive_completed_tasks_where(|task| {
+            task.completed_at.is_some_and(|completed_at| completed_at.0 < cutoff)
+        })
+    }
+
+    fn archive_completed_tasks_where<F>(&mut self, should_archive: F) -> usize
+    where
+        F: Fn(&Task) -> bool,
+    {
+        let task_ids: Vec<usize> = self
+            .active_tasks()
+            .filter(|t| t.status == TaskStatus::Completed && should_archive(t))
+            .map(|t| t.id)
+            .collect();
+
+        self.begin_operation();
+        for task_id in &task_ids {
+            self.archive_task(*task_id);
+        }
+        self.end_operation();
+
+        task_ids.len()
+    }
+
+    pub fn unarchive_task(&mut self, task_id: usize) -> Option<Task> {
+        let archive_index = self.archived_tasks.iter().position(|t| t.id == task_id)?;
+        let task = self.archived_tasks.remove(archive_index);
+
+        self.record(Change::Unarchived {
+            index: self.tasks.len(),
+            task: task.clone(),
+        });
+        self.tasks.push(task.clone());
+
+        Some(task)
+    }
+
+    // Archived tasks whose title or description contains text, ignoring case
+    pub fn search_archive(&self, text: &str) -> Vec<&Task> {
+        let text = text.to_lowercase();
+
+        self.archived_tasks
+            .iter()
+            .filter(|t| {
+                t.title.to_lowercase().contains(&text)
+                    || t
+                        .description
+                        .as_ref()
+                        .is_some_and(|d| d.to_lowercase().contains(&text))
+            })
+            .collect()
+    }
+
+    // Changes tasks and archived tasks to the given ones as a single undo step,
+    // returns the number of tasks that were changed
+    pub fn replace_tasks(&mut self, tasks: Vec<Task>, archived_tasks: Vec<Task>) -> usize {
+        let mut task_ids: Vec<usize> = self
+            .tasks
+            .iter()
+            .chain(&self.archived_tasks)
+            .chain(&tasks)
+            .chain(&archived_tasks)
+            .map(|t| t.id)
+            .collect();
+        task_ids.sort();
+        task_ids.dedup();
+
+        let mut changed = 0;
+        self.begin_operation();
+        for task_id in task_ids {
+            let wanted_task = tasks.iter().find(|t| t.id == task_id);
+            let wanted_archived_task = archived_tasks.iter().find(|t| t.id == task_id);
+            let archived_task = self.archived_tasks.iter().find(|t| t.id == task_id);
+
+            if archived_task.is_some() && archived_task == wanted_archived_task {
+                continue;
+            }
+            if archived_task.is_none() && self.tasks.iter().find(|t| t.id == task_id) == wanted_task {
+                continue;
+            }
+            changed += 1;
+
+            // Archived tasks are changed in the list and archived again
+            if archived_task.is_some() {
+                self.unarchive_task(task_id);
+            }
+
+            let wanted = match wanted_task.or(wanted_archived_task) {
+                Some(wanted) => wanted.clone(),
+                None => {
+                    if let Some(task_index) = self.tasks.iter().position(|t| t.id == task_id) {
+                        let task = self.tasks.remove(task_index);
+                        self.record(Change::Deleted { index: task_index, task });
+                    }
+                    continue;
+                }
+            };
+
+            if self.tasks.iter().any(|t| t.id == task_id) {
+                self.update_task(task_id, |task| *task = wanted);
+            } else {
+                self.record(Change::Added {
+                    index: self.tasks.len(),
+                    task: wanted.clone(),
+                });
+                self.tasks.push(wanted);
+            }
+
+            if wanted_archived_task.is_some() {
+                if let Some(task_index) = self.tasks.iter().position(|t| t.id == task_id) {
+                    self.archive_task_at(task_index);
+                }
+            }
         }
+        self.end_operation();
+
+        self.task_counter = self.task_counter.max(self.get_highest_task_id());
+        changed
+    }
+
+    // Groups the changes made until end_operation into a single undo step
+    pub fn begin_operation(&mut self) {
+        self.history.begin_operation();
     }
 
-    pub fn delete_task(&mut self, task_id: usize) -> Result<(), ()> {
-        let task_index = self.tasks.iter().position(|t| t.id == task_id);
+    pub fn end_operation(&mut self) {
+        self.history.end_operation();
+    }
+
+    pub fn undo(&mut self) -> Option<Operation> {
+        let operation = self.history.undo(&mut self.tasks, &mut self.archived_tasks)?;
 
-        if let Some(task_index) = task_index {
-            self.tasks.remove(task_index);
-            Ok(())
-        } else {
-            Err(())
+        for change in operation.changes.iter().rev() {
+            self.log_change(&change.inverse());
         }
+
+        Some(operation)
     }
 
-    // fn to use persistence::save_tasks_to_csv
-    pub fn save_tasks_to_csv(&self) {
-        let save_result = persistence::save_tasks_to_csv(&self.tasks);
+    pub fn redo(&mut self) -> Option<Operation> {
+        let operation = self.history.redo(&mut self.tasks, &mut self.archived_tasks)?;
 
-        match save_result {
-            Ok(_) => println!("Tasks saved to csv"),
-            Err(e) => println!("Error saving tasks to csv: {}", e),
+        for change in &operation.changes {
+            self.log_change(change);
         }
+
+        Some(operation)
+    }
+
+    // Records an already applied change for undo and for the event log
+    fn record(&mut self, change: Change) {
+        self.log_change(&change);
+        self.history.record(change);
+    }
+
+    fn log_change(&mut self, change: &Change) {
+        let events = Event::from_change(change, NaiveDateTimeWrapper::now());
+        self.pending_events.extend(events);
+    }
+
+    // Refuses to save if another instance saved since we loaded,
+    // unless overwrite_external_changes is set. The stored tasks are backed up
+    // first when they change, keeping backup_retention backups.
+    pub fn save_tasks(&mut self, overwrite_external_changes: bool, backup_retention: usize) -> Result<(), SaveError> {
+        let _lock = self.storage.lock().map_err(SaveError::Io)?;
+
+        let revision = Some(self.storage.revision().map_err(SaveError::Io)?);
+        if !overwrite_external_changes && revision != self.stored_revision {
+            return Err(SaveError::ExternallyModified);
+        }
+
+        let (their_tasks, their_archived_tasks) = self.storage.load_tasks().map_err(SaveError::Io)?;
+
+        let has_changes = self.tasks != self.stored_tasks || self.archived_tasks != self.stored_archived_tasks;
+        if has_changes || overwrite_external_changes {
+            self.storage.back_up(backup_retention).map_err(SaveError::Io)?;
+        }
+
+        // Snapshot of what is stored before our changes, in case there was nothing to load
+        if self
+            .storage
+            .initialize_log(&self.stored_tasks, &self.stored_archived_tasks)
+            .map_err(SaveError::Io)?
+        {
+            self.logged_events = 0;
+        }
+
+        // Other instances only ever add to or remove from the archive through their
+        // own saves, so their changes are merged in without asking
+        let (merged_archived_tasks, _) = merge::merge_tasks(
+            &self.stored_archived_tasks,
+            &self.archived_tasks,
+            &their_archived_tasks,
+        );
+        self.archived_tasks = merged_archived_tasks;
+
+        // What another instance saved is replaced by our tasks, so the log
+        // gets the changes from their tasks to ours instead of our own changes
+        if revision != self.stored_revision {
+            self.pending_events = event_log::events_between(
+                (&their_tasks, &their_archived_tasks),
+                (&self.tasks, &self.archived_tasks),
+                NaiveDateTimeWrapper::now(),
+            );
+            self.logged_events = self.storage.event_count().map_err(SaveError::Io)?;
+        }
+
+        self.logged_events = self
+            .storage
+            .write_tasks(
+                &self.tasks,
+                &self.archived_tasks,
+                &self.pending_events,
+                self.logged_events,
+                self.task_counter,
+            )
+            .map_err(SaveError::Io)?;
+        self.pending_events.clear();
+
+        self.stored_tasks = self.tasks.clone();
+        self.stored_archived_tasks = self.archived_tasks.clone();
+        self.stored_revision = Some(self.storage.revision().map_err(SaveError::Io)?);
+        Ok(())
+    }
+
+    // Reloads the stored tasks and merges changes made by other instances into ours
+    pub fn merge_external_changes(&mut self) -> Result<MergeReport, Box<dyn Error>> {
+        let _lock = self.storage.lock()?;
+
+        // Rows that can't be read were reported when loading and are missing on both sides
+        let (their_tasks, their_archived_tasks) = self.storage.load_tasks()?;
+        let (merged_tasks, report) = merge::merge_tasks(&self.stored_tasks, &self.tasks, &their_tasks);
+        let (merged_archived_tasks, _) =
+            merge::merge_tasks(&self.stored_archived_tasks, &self.archived_tasks, &their_archived_tasks);
+
+        // The log holds their changes now, what gets logged on save is what the
+        // merge changed in their tasks
+        self.pending_events = event_log::events_between(
+            (&their_tasks, &their_archived_tasks),
+            (&merged_tasks, &merged_archived_tasks),
+            NaiveDateTimeWrapper::now(),
+        );
+        self.logged_events = self.storage.event_count()?;
+
+        self.tasks = merged_tasks;
+        self.archived_tasks = merged_archived_tasks;
+        self.stored_tasks = their_tasks;
+        self.stored_archived_tasks = their_archived_tasks;
+        self.stored_revision = Some(self.storage.revision()?);
+        self.task_counter = self.task_counter.max(self.get_highest_task_id());
+
+        Ok(report)
     }
 
     pub fn get_task_by_id(&self, task_id: usize) -> Option<&Task> {
         self.tasks.iter().find(|t| t.id == task_id)
     }
 
-    pub fn load_tasks_from_csv(&self) -> Result<Vec<Task>, Box<dyn Error>> {
-        let load_result = persistence::load_tasks_from_csv();
+    pub fn get_active_task_by_id(&self, task_id: usize) -> Option<&Task> {
+        self.active_tasks().find(|t| t.id == task_id)
+    }
+
+    // Tasks that are not in trash
+    pub fn active_tasks(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.iter().filter(|t| !t.is_deleted())
+    }
+
+    pub fn trashed_tasks(&self) -> impl Iterator<Item = &Task> {
+        self.tasks.iter().filter(|t| t.is_deleted())
+    }
+
+    // Returns the problems found in the stored tasks, tasks with errors are left out
+    pub fn load_tasks(&mut self) -> Result<Vec<String>, Box<dyn Error>> {
+        let _lock = self.storage.lock()?;
+        let loaded_list = self.storage.load_list()?;
+
+        self.stored_tasks = loaded_list.tasks.clone();
+        self.stored_archived_tasks = loaded_list.archived_tasks.clone();
+        self.stored_revision = Some(self.storage.revision()?);
+        self.tasks = loaded_list.tasks;
+        self.archived_tasks = loaded_list.archived_tasks;
+        self.logged_events = loaded_list.logged_events;
+        self.task_counter = loaded_list.task_counter;
+
+        Ok(loaded_list.problems)
+    }
+
+    pub fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
 
-        load_result
+    // Loads and saves with storage from now on, which must hold the same tasks
+    pub fn use_storage(&mut self, storage: Box<dyn Storage>) -> Result<(), Box<dyn Error>> {
+        self.stored_revision = Some(storage.revision()?);
+        self.logged_events = storage.event_count()?;
+        self.storage = storage;
+        Ok(())
     }
 
     pub fn update_task_counter(&mut self, task_counter: usize) {
@@ -108,7 +538,7 @@ impl TaskList {
     pub fn get_highest_task_id(&self) -> usize {
         let mut highest_task_id = 0;
 
-        for task in &self.tasks {
+        for task in self.tasks.iter().chain(self.archived_tasks.iter()) {
             if task.id > highest_task_id {
                 highest_task_id = task.id;
             }
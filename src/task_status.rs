@@ -1,7 +1,7 @@
 use std::str::FromStr;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum TaskStatus {
     NotStarted,
     InProgress,
@@ -17,12 +17,30 @@ impl Serialize for TaskStatus {
     }
 }
 
-impl ToString for TaskStatus {
-    fn to_string(&self) -> String {
+impl TaskStatus {
+    // All statuses in the order a task normally goes through them
+    pub fn all() -> Vec<TaskStatus> {
+        vec![TaskStatus::NotStarted, TaskStatus::InProgress, TaskStatus::Completed]
+    }
+}
+
+impl<'de> Deserialize<'de> for TaskStatus {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let status = String::deserialize(deserializer)?;
+        TaskStatus::from_str(&status)
+            .map_err(|_| serde::de::Error::custom(format!("invalid status: {}", status)))
+    }
+}
+
+impl std::fmt::Display for TaskStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
-            TaskStatus::NotStarted => "Not Started".to_string(),
-            TaskStatus::InProgress => "In Progress".to_string(),
-            TaskStatus::Completed => "Completed".to_string(),
+            TaskStatus::NotStarted => write!(f, "Not Started"),
+            TaskStatus::InProgress => write!(f, "In Progress"),
+            TaskStatus::Completed => write!(f, "Completed"),
         }
     }
 }
@@ -0,0 +1,271 @@
+use chrono::NaiveDateTime;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::priority::Priority;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+// Taskwarrior writes all times in UTC in this format
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+// A task as written by `task export`, fields we don't use are left out
+#[derive(Debug, Serialize, Deserialize)]
+struct TaskwarriorTask {
+    uuid: String,
+    description: String,
+    status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    modified: Option<String>,
+    // Set while a task is being worked on
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    start: Option<String>,
+    // When the task was completed or deleted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Annotation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    entry: Option<String>,
+    description: String,
+}
+
+// Writes tasks like `task export` does. The first tag is the project and the
+// description is written as an annotation.
+pub fn to_taskwarrior_json(tasks: &[&Task], list_id: u32) -> Result<String, String> {
+    let now = NaiveDateTimeWrapper::now();
+
+    let exported: Vec<TaskwarriorTask> = tasks
+        .iter()
+        .map(|task| {
+            let entry = task.created_at.unwrap_or(now);
+            let status = match task.status {
+                TaskStatus::Completed => "completed",
+                _ => "pending",
+            };
+            let annotations = task
+                .description
+                .iter()
+                .map(|description| Annotation {
+                    entry: Some(format_time(entry)),
+                    description: description.clone(),
+                })
+                .collect();
+
+            TaskwarriorTask {
+                uuid: task.uid.clone().unwrap_or_else(|| generated_uuid(task, list_id)),
+                description: task.title.clone(),
+                status: status.to_string(),
+                entry: Some(format_time(entry)),
+                modified: Some(format_time(now)),
+                // When work on the task started isn't known
+                start: (task.status == TaskStatus::InProgress).then(|| format_time(entry)),
+                end: task.completed_at.map(format_time),
+                due: task.due_date.map(format_time),
+                project: task.tags.first().cloned(),
+                tags: task.tags.iter().skip(1).cloned().collect(),
+                priority: task.priority.map(|priority| priority_letter(priority).to_string()),
+                annotations,
+            }
+        })
+        .collect();
+
+    serde_json::to_string_pretty(&exported).map_err(|e| e.to_string())
+}
+
+// Reads the output of `task export`. Deleted tasks and recurring templates are
+// left out. The tasks have the UUID of the Taskwarrior task, and id 0 unless
+// they were exported from this list before.
+pub fn from_taskwarrior_json(contents: &str, list_id: u32) -> Result<Vec<Task>, String> {
+    let imported: Vec<TaskwarriorTask> =
+        serde_json::from_str(contents).map_err(|e| format!("Invalid Taskwarrior JSON: {}", e))?;
+    let mut tasks = Vec::new();
+
+    for imported_task in imported {
+        let status = match imported_task.status.as_str() {
+            "completed" => TaskStatus::Completed,
+            "pending" | "waiting" if imported_task.start.is_some() => TaskStatus::InProgress,
+            "pending" | "waiting" => TaskStatus::NotStarted,
+            _ => continue,
+        };
+
+        let mut task = Task::new(task_id_from_uuid(&imported_task.uuid, list_id).unwrap_or(0), imported_task.description);
+        if status == TaskStatus::Completed {
+            let completed_at = imported_task.end.as_deref().map(parse_time).transpose()?;
+            task.set_completed_at(Some(completed_at.unwrap_or_else(NaiveDateTimeWrapper::now)));
+        }
+        task.set_status(status);
+        task.due_date = imported_task.due.as_deref().map(parse_time).transpose()?;
+        task.set_created_at(imported_task.entry.as_deref().map(parse_time).transpose()?);
+        task.set_priority(imported_task.priority.as_deref().and_then(priority_from_letter));
+
+        // Tags can't contain spaces, the project becomes the first tag
+        let tags = imported_task
+            .project
+            .into_iter()
+            .chain(imported_task.tags)
+            .map(|tag| tag.split_whitespace().collect::<Vec<&str>>().join("-"))
+            .filter(|tag| !tag.is_empty())
+            .collect();
+        task.set_tags(tags);
+
+        let annotations: Vec<String> = imported_task.annotations.into_iter().map(|a| a.description).collect();
+        if !annotations.is_empty() {
+            task.set_description(annotations.join("\n"));
+        }
+        task.set_uid(Some(imported_task.uuid));
+
+        tasks.push(task);
+    }
+
+    Ok(tasks)
+}
+
+// Taskwarrior needs a UUID for every task. Tasks that don't have one from an
+// import get one made of their creation time, the list id and the task id, so
+// it stays the same. The version 4 and variant digits are kept in place.
+fn generated_uuid(task: &Task, list_id: u32) -> String {
+    let created = task.created_at.map(|created_at| created_at.0.and_utc().timestamp()).unwrap_or(0);
+    format!(
+        "{:08x}-{:04x}-4{:03x}-8{:03x}-{:012x}",
+        created as u32,
+        list_id >> 16,
+        (list_id >> 4) & 0xfff,
+        list_id & 0xf,
+        task.id
+    )
+}
+
+// Id of the task a UUID from generated_uuid belongs to, None for other UUIDs
+fn task_id_from_uuid(uuid: &str, list_id: u32) -> Option<usize> {
+    let parts: Vec<&str> = uuid.split('-').collect();
+    let [_, list_high, list_middle, list_low, task_id] = parts[..] else {
+        return None;
+    };
+    let uuid_list_id = format!("{}-{}-{}", list_high, list_middle, list_low);
+
+    if uuid_list_id != format!("{:04x}-4{:03x}-8{:03x}", list_id >> 16, (list_id >> 4) & 0xfff, list_id & 0xf) {
+        return None;
+    }
+    usize::from_str_radix(task_id, 16).ok()
+}
+
+fn format_time(date_time: NaiveDateTimeWrapper) -> String {
+    date_time.to_utc().format(TIME_FORMAT).to_string()
+}
+
+fn parse_time(value: &str) -> Result<NaiveDateTimeWrapper, String> {
+    NaiveDateTime::parse_from_str(value, TIME_FORMAT)
+        .map(NaiveDateTimeWrapper::from_utc)
+        .map_err(|_| format!("Invalid date: {}", value))
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'H',
+        Priority::Medium => 'M',
+        Priority::Low => 'L',
+    }
+}
+
+fn priority_from_letter(letter: &str) -> Option<Priority> {
+    match letter {
+        "H" => Some(Priority::High),
+        "M" => Some(Priority::Medium),
+        "L" => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST_ID: u32 = 0x0a1b2c3d;
+
+    fn time(time: &str) -> NaiveDateTimeWrapper {
+        NaiveDateTimeWrapper::parse_from_str(time, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn imports_exported_tasks() {
+        let mut login = Task::new(7, "Fix login".to_string());
+        login.set_created_at(Some(time("2026-10-01 09:30:00")));
+        login.set_due_date(time("2026-10-30 23:59:00"));
+        login.set_status(TaskStatus::InProgress);
+        login.set_priority(Some(Priority::High));
+        login.set_tags(vec!["api".to_string(), "auth".to_string()]);
+        login.set_description("Users get logged out".to_string());
+        let mut release = Task::new(8, "Release".to_string());
+        release.set_created_at(Some(time("2026-09-20 08:00:00")));
+        release.set_status(TaskStatus::Completed);
+        release.set_completed_at(Some(time("2026-10-05 16:45:10")));
+
+        let contents = to_taskwarrior_json(&[&login, &release], LIST_ID).unwrap();
+        let tasks = from_taskwarrior_json(&contents, LIST_ID).unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        for (task, original) in tasks.iter().zip([&login, &release]) {
+            assert_eq!(task.id, original.id);
+            assert_eq!(task.title, original.title);
+            assert_eq!(task.description, original.description);
+            assert_eq!(task.due_date, original.due_date);
+            assert_eq!(task.status, original.status);
+            assert_eq!(task.completed_at, original.completed_at);
+            assert_eq!(task.priority, original.priority);
+            assert_eq!(task.tags, original.tags);
+            assert_eq!(task.created_at, original.created_at);
+        }
+        // Exported again, the tasks keep their UUIDs
+        let uuids: Vec<Option<String>> = tasks.iter().map(|t| t.uid.clone()).collect();
+        let exported_again = to_taskwarrior_json(&tasks.iter().collect::<Vec<&Task>>(), LIST_ID).unwrap();
+        let tasks_again = from_taskwarrior_json(&exported_again, LIST_ID).unwrap();
+        assert_eq!(tasks_again.iter().map(|t| t.uid.clone()).collect::<Vec<Option<String>>>(), uuids);
+    }
+
+    #[test]
+    fn tasks_of_other_lists_get_no_id() {
+        let task = Task::new(7, "Fix login".to_string());
+        let contents = to_taskwarrior_json(&[&task], LIST_ID).unwrap();
+
+        assert_eq!(from_taskwarrior_json(&contents, LIST_ID + 1).unwrap()[0].id, 0);
+        assert_eq!(task_id_from_uuid("c5a1e2b4-12f3-4a5b-9c6d-7e8f9a0b1c2d", LIST_ID), None);
+    }
+
+    #[test]
+    fn reads_tasks_exported_by_taskwarrior() {
+        let contents = r#"[
+            {"id":1,"description":"Call Mom","entry":"20261001T093000Z","modified":"20261001T093000Z",
+             "project":"Home Chores","status":"pending","start":"20261002T080000Z",
+             "tags":["phone"],"urgency":1.9,"uuid":"c5a1e2b4-12f3-4a5b-9c6d-7e8f9a0b1c2d",
+             "annotations":[{"entry":"20261001T093100Z","description":"About the weekend"},
+                            {"entry":"20261001T093200Z","description":"Before 6pm"}]},
+            {"description":"Old","entry":"20261001T093000Z","status":"deleted","uuid":"d5a1e2b4-12f3-4a5b-9c6d-7e8f9a0b1c2d"},
+            {"description":"Weekly","entry":"20261001T093000Z","status":"recurring","uuid":"e5a1e2b4-12f3-4a5b-9c6d-7e8f9a0b1c2d"}
+        ]"#;
+
+        let tasks = from_taskwarrior_json(contents, LIST_ID).unwrap();
+
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, 0);
+        assert_eq!(tasks[0].status, TaskStatus::InProgress);
+        assert_eq!(tasks[0].tags, vec!["Home-Chores".to_string(), "phone".to_string()]);
+        assert_eq!(tasks[0].description.as_deref(), Some("About the weekend\nBefore 6pm"));
+        assert_eq!(tasks[0].uid.as_deref(), Some("c5a1e2b4-12f3-4a5b-9c6d-7e8f9a0b1c2d"));
+        assert!(from_taskwarrior_json("{}", LIST_ID).is_err());
+    }
+}
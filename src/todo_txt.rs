@@ -0,0 +1,230 @@
+use chrono::{NaiveDate, NaiveTime};
+
+use crate::naive_date_time_wrapper::NaiveDateTimeWrapper;
+use crate::priority::Priority;
+use crate::task::Task;
+use crate::task_status::TaskStatus;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+// Tasks due on a day without a time are due at the end of it
+const END_OF_DAY: (u32, u32) = (23, 59);
+// todo.txt has no status between open and done, so it's kept as a key:value
+const IN_PROGRESS: &str = "status:in-progress";
+
+// Writes one line per task like `(A) 2026-10-01 Fix login +api @work due:2026-10-30`.
+// Tags starting with @ are written as contexts, all others as projects.
+// Descriptions don't fit on the line and are left out.
+pub fn to_todo_txt(tasks: &[&Task]) -> String {
+    let mut lines = Vec::new();
+
+    for task in tasks {
+        let mut words = Vec::new();
+
+        if task.status == TaskStatus::Completed {
+            words.push("x".to_string());
+            // The completion date has to come first, an unknown one is left out
+            // along with the creation date, as a single date would be taken for it
+            if let Some(completed_at) = task.completed_at {
+                words.push(completed_at.0.format(DATE_FORMAT).to_string());
+                if let Some(created_at) = task.created_at {
+                    words.push(created_at.0.format(DATE_FORMAT).to_string());
+                }
+            }
+        } else {
+            if let Some(priority) = task.priority {
+                words.push(format!("({})", priority_letter(priority)));
+            }
+            if let Some(created_at) = task.created_at {
+                words.push(created_at.0.format(DATE_FORMAT).to_string());
+            }
+        }
+
+        // Line breaks would start a new task
+        words.push(task.title.split_whitespace().collect::<Vec<&str>>().join(" "));
+        for tag in &task.tags {
+            if tag.starts_with('@') {
+                words.push(tag.clone());
+            } else {
+                words.push(format!("+{}", tag));
+            }
+        }
+        if let Some(due_date) = task.due_date {
+            words.push(format!("due:{}", due_date.0.format(DATE_FORMAT)));
+        }
+        if task.status == TaskStatus::InProgress {
+            words.push(IN_PROGRESS.to_string());
+        }
+        // Completed tasks lose their priority in todo.txt, pri: keeps it
+        if let (TaskStatus::Completed, Some(priority)) = (&task.status, task.priority) {
+            words.push(format!("pri:{}", priority_letter(priority)));
+        }
+
+        lines.push(words.join(" "));
+    }
+
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+// Reads one task per line, blank lines are skipped. The tasks have id 0.
+// Key:value pairs other than due:, status: and pri: stay in the title.
+pub fn from_todo_txt(contents: &str) -> Result<Vec<Task>, String> {
+    let mut tasks = Vec::new();
+
+    for (index, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let task = parse_line(line).map_err(|e| format!("Line {}: {}", index + 1, e))?;
+        tasks.push(task);
+    }
+
+    Ok(tasks)
+}
+
+fn parse_line(line: &str) -> Result<Task, String> {
+    let mut words = line.split_whitespace().peekable();
+
+    let completed = words.next_if_eq(&"x").is_some();
+    let mut priority = words.next_if(|word| parse_priority(word).is_some()).and_then(parse_priority);
+    let first_date = words.next_if(|word| parse_date(word).is_some()).and_then(parse_date);
+    let second_date = words.next_if(|word| parse_date(word).is_some()).and_then(parse_date);
+    // A completed task has its completion date first, then the creation date
+    let (completed_at, created_at) = match (completed, first_date, second_date) {
+        (true, completed_at, created_at) => (completed_at, created_at),
+        (false, created_at, _) => (None, created_at),
+    };
+
+    let mut title_words = Vec::new();
+    let mut tags = Vec::new();
+    let mut due_date = None;
+    let mut in_progress = false;
+
+    for word in words {
+        if let Some(project) = word.strip_prefix('+').filter(|project| !project.is_empty()) {
+            tags.push(project.to_string());
+            continue;
+        }
+        if word.len() > 1 && word.starts_with('@') {
+            tags.push(word.to_string());
+            continue;
+        }
+
+        match word.split_once(':') {
+            Some(("due", value)) => {
+                let date = parse_date(value).ok_or_else(|| format!("Invalid due date: {}", value))?;
+                let (hour, minute) = END_OF_DAY;
+                let time = NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or_default();
+                due_date = Some(NaiveDateTimeWrapper::new(date.and_time(time)));
+            }
+            Some(("status", "in-progress")) => in_progress = true,
+            Some(("pri", letter)) if priority.is_none() => {
+                priority = parse_priority(&format!("({})", letter));
+            }
+            _ => title_words.push(word),
+        }
+    }
+
+    if title_words.is_empty() {
+        return Err("Task has no title".to_string());
+    }
+
+    let mut task = Task::new(0, title_words.join(" "));
+    task.due_date = due_date;
+    task.set_tags(tags);
+    task.set_priority(priority);
+    task.set_created_at(created_at.map(|date| NaiveDateTimeWrapper::new(date.and_time(NaiveTime::MIN))));
+    if completed {
+        task.set_status(TaskStatus::Completed);
+        let completed_at = completed_at.map(|date| NaiveDateTimeWrapper::new(date.and_time(NaiveTime::MIN)));
+        task.set_completed_at(Some(completed_at.unwrap_or_else(NaiveDateTimeWrapper::now)));
+    } else if in_progress {
+        task.set_status(TaskStatus::InProgress);
+    }
+
+    Ok(task)
+}
+
+fn priority_letter(priority: Priority) -> char {
+    match priority {
+        Priority::High => 'A',
+        Priority::Medium => 'B',
+        Priority::Low => 'C',
+    }
+}
+
+// (A) is high, (B) medium and (C) to (Z) low
+fn parse_priority(word: &str) -> Option<Priority> {
+    let letter = word.strip_prefix('(')?.strip_suffix(')')?;
+
+    match letter {
+        "A" => Some(Priority::High),
+        "B" => Some(Priority::Medium),
+        _ if letter.len() == 1 && letter.chars().all(|c| c.is_ascii_uppercase()) => Some(Priority::Low),
+        _ => None,
+    }
+}
+
+fn parse_date(word: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(word, DATE_FORMAT).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDateTimeWrapper {
+        NaiveDateTimeWrapper::parse_from_str(date, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn imports_exported_tasks() {
+        let mut open = Task::new(1, "Fix login".to_string());
+        open.set_priority(Some(Priority::High));
+        open.set_created_at(Some(date("2026-10-01 00:00:00")));
+        open.set_tags(vec!["api".to_string(), "@work".to_string()]);
+        open.set_due_date(date("2026-10-30 23:59:00"));
+        open.set_status(TaskStatus::InProgress);
+        let mut done = Task::new(2, "Ship release".to_string());
+        done.set_status(TaskStatus::Completed);
+        done.set_completed_at(Some(date("2026-10-05 00:00:00")));
+        done.set_created_at(Some(date("2026-09-20 00:00:00")));
+        done.set_priority(Some(Priority::Medium));
+
+        let contents = to_todo_txt(&[&open, &done]);
+        assert_eq!(
+            contents,
+            "(A) 2026-10-01 Fix login +api @work due:2026-10-30 status:in-progress\n\
+             x 2026-10-05 2026-09-20 Ship release pri:B\n"
+        );
+
+        let tasks = from_todo_txt(&contents).unwrap();
+        for (task, original) in tasks.iter().zip([&open, &done]) {
+            assert_eq!(task.title, original.title);
+            assert_eq!(task.status, original.status);
+            assert_eq!(task.priority, original.priority);
+            assert_eq!(task.tags, original.tags);
+            assert_eq!(task.due_date, original.due_date);
+            assert_eq!(task.created_at, original.created_at);
+            assert_eq!(task.completed_at, original.completed_at);
+        }
+    }
+
+    #[test]
+    fn reads_todo_txt_written_by_other_applications() {
+        let tasks = from_todo_txt("\n(D) Call Mom @phone note:later\nx Buy milk\n").unwrap();
+
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].title, "Call Mom note:later");
+        assert_eq!(tasks[0].priority, Some(Priority::Low));
+        assert_eq!(tasks[0].tags, vec!["@phone".to_string()]);
+        assert_eq!(tasks[0].created_at, None);
+        assert_eq!(tasks[1].status, TaskStatus::Completed);
+        assert!(tasks[1].completed_at.is_some());
+    }
+
+    #[test]
+    fn reports_the_line_of_invalid_tasks() {
+        assert_eq!(from_todo_txt("Fine\n(A) +tag\n"), Err("Line 2: Task has no title".to_string()));
+        assert!(from_todo_txt("Pay rent due:soon").is_err());
+    }
+}
@@ -0,0 +1,433 @@
+use std::io;
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::command_line;
+use crate::task::Task;
+use crate::task_list::TaskList;
+use crate::task_status::TaskStatus;
+
+// How often the screen is redrawn when no key is pressed, so overdue
+// highlighting stays up to date
+const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+
+const HELP_LINE: &str = "a add  t title  e description  u due date  s status  d delete  / filter  z undo  y redo  q quit";
+
+enum Input {
+    Filter,
+    AddTitle,
+    AddDescription(usize),
+    AddDueDate(usize),
+    EditTitle(usize),
+    EditDescription(usize),
+    EditDueDate(usize),
+    ConfirmDelete(usize),
+}
+
+impl Input {
+    fn prompt(&self) -> String {
+        match self {
+            Input::Filter => "Filter".to_string(),
+            Input::AddTitle => "Title of the new task".to_string(),
+            Input::AddDescription(_) => "Description (optional)".to_string(),
+            Input::AddDueDate(_) => format!("Due date {} (optional)", command_line::DUE_DATE_FORMATS),
+            Input::EditTitle(task_id) => format!("New title for task {}", task_id),
+            Input::EditDescription(task_id) => format!("New description for task {}", task_id),
+            Input::EditDueDate(task_id) => {
+                format!("New due date for task {} ({})", task_id, command_line::DUE_DATE_FORMATS)
+            }
+            Input::ConfirmDelete(task_id) => format!("Move task {} to trash? (y/n)", task_id),
+        }
+    }
+}
+
+struct App {
+    table_state: TableState,
+    filter: String,
+    input: Option<Input>,
+    buffer: String,
+    message: String,
+    quit: bool,
+}
+
+// Runs the full-screen interface until the user quits
+pub fn run(list_of_tasks: &mut TaskList) -> io::Result<()> {
+    let mut terminal = ratatui::init();
+    let result = run_app(&mut terminal, list_of_tasks);
+    ratatui::restore();
+
+    result
+}
+
+fn run_app(terminal: &mut DefaultTerminal, list_of_tasks: &mut TaskList) -> io::Result<()> {
+    let mut app = App {
+        table_state: TableState::default().with_selected(Some(0)),
+        filter: String::new(),
+        input: None,
+        buffer: String::new(),
+        message: String::new(),
+        quit: false,
+    };
+
+    while !app.quit {
+        terminal.draw(|frame| draw(frame, &mut app, list_of_tasks))?;
+
+        if !event::poll(REDRAW_INTERVAL)? {
+            continue;
+        }
+
+        if let Event::Key(key) = event::read()? {
+            if key.kind == KeyEventKind::Press {
+                handle_key(&mut app, list_of_tasks, key);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn visible_tasks<'a>(list_of_tasks: &'a TaskList, filter: &str) -> Vec<&'a Task> {
+    let filter = filter.to_lowercase();
+
+    list_of_tasks
+        .active_tasks()
+        .filter(|t| {
+            filter.is_empty()
+                || t.title.to_lowercase().contains(&filter)
+                || t
+                    .description
+                    .as_ref()
+                    .is_some_and(|d| d.to_lowercase().contains(&filter))
+        })
+        .collect()
+}
+
+fn selected_task_id(app: &App, list_of_tasks: &TaskList) -> Option<usize> {
+    let tasks = visible_tasks(list_of_tasks, &app.filter);
+
+    app.table_state
+        .selected()
+        .and_then(|index| tasks.get(index))
+        .map(|t| t.id)
+}
+
+pub fn status_style(status: &TaskStatus) -> Style {
+    match status {
+        TaskStatus::NotStarted => Style::default(),
+        TaskStatus::InProgress => Style::default().fg(Color::Yellow),
+        TaskStatus::Completed => Style::default().fg(Color::Green),
+    }
+}
+
+fn draw(frame: &mut Frame, app: &mut App, list_of_tasks: &TaskList) {
+    let [main_area, footer_area] =
+        Layout::vertical([Constraint::Min(3), Constraint::Length(3)]).areas(frame.area());
+    let [table_area, detail_area] =
+        Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(main_area);
+
+    let tasks = visible_tasks(list_of_tasks, &app.filter);
+    if tasks.is_empty() {
+        app.table_state.select(None);
+    } else {
+        let selected = app.table_state.selected().unwrap_or(0).min(tasks.len() - 1);
+        app.table_state.select(Some(selected));
+    }
+
+    let rows = tasks.iter().map(|task| {
+        let due_date = task.due_date.map(|d| d.to_string()).unwrap_or_default();
+        let due_date_style = if task.is_overdue() {
+            Style::default().fg(Color::Red)
+        } else {
+            Style::default()
+        };
+
+        Row::new(vec![
+            Cell::from(task.id.to_string()),
+            Cell::from(task.title.clone()),
+            Cell::from(task.status.to_string()).style(status_style(&task.status)),
+            Cell::from(due_date).style(due_date_style),
+        ])
+    });
+
+    let title = if app.filter.is_empty() {
+        format!(" Tasks ({}) ", tasks.len())
+    } else {
+        format!(" Tasks ({}) - filter: {} ", tasks.len(), app.filter)
+    };
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(5),
+            Constraint::Min(10),
+            Constraint::Length(12),
+            Constraint::Length(19),
+        ],
+    )
+    .header(Row::new(vec!["Id", "Title", "Status", "Due date"]).style(Style::default().add_modifier(Modifier::BOLD)))
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    frame.render_stateful_widget(table, table_area, &mut app.table_state);
+
+    let selected_task = app
+        .table_state
+        .selected()
+        .and_then(|index| tasks.get(index));
+    let detail = match selected_task {
+        Some(task) => task_detail(task),
+        None => vec![Line::from("No task selected")],
+    };
+    frame.render_widget(
+        Paragraph::new(detail)
+            .wrap(Wrap { trim: false })
+            .block(Block::default().borders(Borders::ALL).title(" Detail ")),
+        detail_area,
+    );
+
+    let footer = match &app.input {
+        Some(input) => format!("{}: {}_", input.prompt(), app.buffer),
+        None => HELP_LINE.to_string(),
+    };
+    frame.render_widget(
+        Paragraph::new(footer).block(Block::default().borders(Borders::ALL).title(app.message.clone())),
+        footer_area,
+    );
+}
+
+fn task_detail(task: &Task) -> Vec<Line<'static>> {
+    let mut lines = vec![
+        Line::from(Span::styled(
+            format!("{} - {}", task.id, task.title),
+            Style::default().add_modifier(Modifier::BOLD),
+        )),
+        Line::from(""),
+        Line::from(format!(
+            "Description: {}",
+            task.description.clone().unwrap_or("Not provided".to_string())
+        )),
+    ];
+
+    let due_date = task
+        .due_date
+        .map(|d| d.to_string())
+        .unwrap_or("Not provided".to_string());
+    if task.is_overdue() {
+        lines.push(Line::from(Span::styled(
+            format!("Due date: {} (overdue)", due_date),
+            Style::default().fg(Color::Red),
+        )));
+    } else {
+        lines.push(Line::from(format!("Due date: {}", due_date)));
+    }
+
+    lines.push(Line::from(Span::styled(
+        format!("Status: {}", task.status),
+        status_style(&task.status),
+    )));
+    if let Some(completed_at) = task.completed_at {
+        lines.push(Line::from(format!("Completed: {}", completed_at)));
+    }
+    if let Some(priority) = task.priority {
+        lines.push(Line::from(format!("Priority: {}", priority)));
+    }
+    if !task.tags.is_empty() {
+        lines.push(Line::from(Span::styled(
+            format!("Tags: +{}", task.tags.join(" +")),
+            Style::default().fg(Color::Cyan),
+        )));
+    }
+
+    lines
+}
+
+fn handle_key(app: &mut App, list_of_tasks: &mut TaskList, key: KeyEvent) {
+    app.message.clear();
+
+    if app.input.is_some() {
+        handle_input_key(app, list_of_tasks, key);
+        return;
+    }
+
+    let task_count = visible_tasks(list_of_tasks, &app.filter).len();
+    let selected_task_id = selected_task_id(app, list_of_tasks);
+
+    match key.code {
+        KeyCode::Char('q') => app.quit = true,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => app.quit = true,
+        KeyCode::Down | KeyCode::Char('j') => app.table_state.select_next(),
+        KeyCode::Up | KeyCode::Char('k') => app.table_state.select_previous(),
+        KeyCode::PageDown => app.table_state.scroll_down_by(10),
+        KeyCode::PageUp => app.table_state.scroll_up_by(10),
+        KeyCode::Home => app.table_state.select_first(),
+        KeyCode::End => app.table_state.select(Some(task_count.saturating_sub(1))),
+        KeyCode::Char('/') => {
+            app.buffer = app.filter.clone();
+            app.input = Some(Input::Filter);
+        }
+        KeyCode::Esc => app.filter.clear(),
+        KeyCode::Char('a') => start_input(app, Input::AddTitle, String::new()),
+        KeyCode::Char('z') => {
+            app.message = match list_of_tasks.undo() {
+                Some(operation) => format!("Undone: {}", operation),
+                None => "Nothing to undo".to_string(),
+            }
+        }
+        KeyCode::Char('y') => {
+            app.message = match list_of_tasks.redo() {
+                Some(operation) => format!("Redone: {}", operation),
+                None => "Nothing to redo".to_string(),
+            }
+        }
+        _ => {
+            let task = match selected_task_id.and_then(|id| list_of_tasks.get_active_task_by_id(id)) {
+                Some(task) => task,
+                None => return,
+            };
+
+            match key.code {
+                KeyCode::Char('t') => start_input(app, Input::EditTitle(task.id), task.title.clone()),
+                KeyCode::Char('e') => start_input(
+                    app,
+                    Input::EditDescription(task.id),
+                    task.description.clone().unwrap_or_default(),
+                ),
+                KeyCode::Char('u') => start_input(
+                    app,
+                    Input::EditDueDate(task.id),
+                    task.due_date
+                        .map(|d| d.0.format("%d.%m.%Y %H:%M").to_string())
+                        .unwrap_or_default(),
+                ),
+                KeyCode::Char('s') => {
+                    let next_status = match task.status {
+                        TaskStatus::NotStarted => TaskStatus::InProgress,
+                        TaskStatus::InProgress => TaskStatus::Completed,
+                        TaskStatus::Completed => TaskStatus::NotStarted,
+                    };
+                    let task_id = task.id;
+                    list_of_tasks.update_task_status(task_id, next_status);
+                }
+                KeyCode::Char('d') | KeyCode::Delete => {
+                    start_input(app, Input::ConfirmDelete(task.id), String::new())
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+fn start_input(app: &mut App, input: Input, buffer: String) {
+    app.input = Some(input);
+    app.buffer = buffer;
+}
+
+fn handle_input_key(app: &mut App, list_of_tasks: &mut TaskList, key: KeyEvent) {
+    let input = match app.input.take() {
+        Some(input) => input,
+        None => return,
+    };
+
+    if let Input::ConfirmDelete(task_id) = input {
+        if key.code == KeyCode::Char('y') {
+            list_of_tasks.delete_task(task_id);
+            app.message = format!("Task {} moved to trash", task_id);
+        }
+        return;
+    }
+
+    match key.code {
+        // Ctrl-C cancels the input like Esc, other shortcuts aren't text
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            cancel_input(app, list_of_tasks, input)
+        }
+        KeyCode::Char(_) if key.modifiers.contains(KeyModifiers::CONTROL) => app.input = Some(input),
+        KeyCode::Char(c) => {
+            app.buffer.push(c);
+            app.input = Some(input);
+        }
+        KeyCode::Backspace => {
+            app.buffer.pop();
+            app.input = Some(input);
+        }
+        KeyCode::Esc => cancel_input(app, list_of_tasks, input),
+        KeyCode::Enter => {
+            let value = std::mem::take(&mut app.buffer).trim().to_string();
+            submit_input(app, list_of_tasks, input, value);
+        }
+        _ => app.input = Some(input),
+    }
+}
+
+fn cancel_input(app: &mut App, list_of_tasks: &mut TaskList, input: Input) {
+    // An add in progress is finished with whatever was entered so far
+    if let Input::AddDescription(_) | Input::AddDueDate(_) = input {
+        list_of_tasks.end_operation();
+    }
+    app.buffer.clear();
+}
+
+fn submit_input(app: &mut App, list_of_tasks: &mut TaskList, input: Input, value: String) {
+    match input {
+        Input::Filter => {
+            app.filter = value;
+            app.table_state.select_first();
+        }
+        Input::AddTitle => {
+            if value.is_empty() {
+                app.message = "Title cannot be empty".to_string();
+                return;
+            }
+            list_of_tasks.begin_operation();
+            let task_id = list_of_tasks.add_task(value);
+            app.table_state.select_last();
+            app.input = Some(Input::AddDescription(task_id));
+        }
+        Input::AddDescription(task_id) => {
+            if !value.is_empty() {
+                list_of_tasks.update_task_description(task_id, value);
+            }
+            app.input = Some(Input::AddDueDate(task_id));
+        }
+        Input::AddDueDate(task_id) => {
+            if value.is_empty() {
+                list_of_tasks.end_operation();
+                app.message = format!("Task {} added", task_id);
+                return;
+            }
+            match command_line::parse_due_date(&value) {
+                Ok(due_date) => {
+                    list_of_tasks.update_task_due_date(task_id, due_date);
+                    list_of_tasks.end_operation();
+                    app.message = format!("Task {} added", task_id);
+                }
+                Err(e) => {
+                    app.message = format!("{}, please try again", e);
+                    app.buffer = value;
+                    app.input = Some(Input::AddDueDate(task_id));
+                }
+            }
+        }
+        Input::EditTitle(task_id) => {
+            if value.is_empty() {
+                app.message = "Title cannot be empty".to_string();
+                return;
+            }
+            list_of_tasks.update_task_title(task_id, value);
+        }
+        // An empty description removes it
+        Input::EditDescription(task_id) if value.is_empty() => list_of_tasks.clear_task_description(task_id),
+        Input::EditDescription(task_id) => list_of_tasks.update_task_description(task_id, value),
+        Input::EditDueDate(task_id) => match command_line::parse_due_date(&value) {
+            Ok(due_date) => list_of_tasks.update_task_due_date(task_id, due_date),
+            Err(e) => app.message = format!("{}, due date was not changed", e),
+        },
+        Input::ConfirmDelete(_) => {}
+    }
+}